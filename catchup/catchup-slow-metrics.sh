@@ -0,0 +1,47 @@
+#!/bin/bash
+
+# Metrics too slow to be collected by `collect all` on every commit are caught up here, one
+# commit and one metric at a time.
+
+set -x
+
+git pull
+
+cargo build --release --bin collect
+
+git clone git@github.com:bevyengine/bevy.git
+cd bevy
+
+git clone -b results git@github.com:bevyengine/twitcher.git results
+
+# Most recent commits first, so that the latest ones are filled in before going back in history
+for commit in `git log --format=%H`
+do
+    dir=results/${commit:0:1}/${commit:1:1}/$commit
+    [ -f $dir/stats.json ] || continue
+    # Command to collect the metric, and the file it leaves in the results of a commit
+    for metric in "diagnostics diagnostics.stats" "test-suite tests.stats" "example-builds examples.stats" "doc-build doc-build-time.clean.stats"
+    do
+        set -- $metric
+        if [ ! -e $dir/$2 ]
+        then
+            gitref=$commit
+            command=$1
+            break 2
+        fi
+    done
+done
+[ ! "$gitref" ] && exit 1
+
+git reset --hard $gitref
+../target/release/collect --merge-results $command
+
+cd results
+git add .
+git commit -m "Add $command for $gitref"
+git push
+cd ..
+rm -rf results
+
+cd ..
+rm -rf bevy
@@ -15,14 +15,26 @@ then
     exit 1
 fi
 gitref=`echo ${gitref#./queue/}`
+# A queued file can name a single metric to measure, when bisecting a change
+metric=`cat ./queue/$gitref`
+
+git clone -b results git@github.com:bevyengine/twitcher.git results
 
 git clone git@github.com:bevyengine/bevy.git
 cd bevy
 git reset --hard $gitref
-../target/release/collect all
+if [ "$metric" ]
+then
+    # Merge with what was already measured on this commit
+    prefix=results/${gitref:0:1}/${gitref:1:1}
+    mkdir -p $prefix
+    cp -r ../$prefix/$gitref $prefix/
+    ../target/release/collect metric "$metric"
+else
+    ../target/release/collect all
+fi
 cd ..
 
-git clone -b results git@github.com:bevyengine/twitcher.git results
 cp -r bevy/results/* results
 cd results
 git add .
@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+/// A criterion benchmark id like `ecs::iteration/iter_simple/foreach`, split into its parts.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkId {
+    pub group: String,
+    pub function: String,
+    pub parameter: Option<String>,
+}
+
+impl BenchmarkId {
+    pub fn parse(id: &str) -> Self {
+        let mut parts = id.splitn(3, '/');
+        Self {
+            group: parts.next().unwrap_or_default().to_string(),
+            function: parts.next().unwrap_or_default().to_string(),
+            parameter: parts.next().map(|p| p.to_string()),
+        }
+    }
+}
+
+/// A short file-safe identifier for a name.
+///
+/// It comes from a stable 128 bits hash of the name, so it doesn't change from one run to the
+/// next or depend on which other names are around, and collisions are too unlikely to matter.
+pub fn file_name(name: &str) -> String {
+    let hash = name
+        .bytes()
+        .fold(0x6c62272e07bb014262b821756295c58d, |hash, byte| {
+            (hash ^ byte as u128).wrapping_mul(0x0000000001000000000000000000013b)
+        });
+    format!("{hash:032x}")
+}
+
+pub(crate) fn fnv1a(value: &[u8]) -> u64 {
+    value.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
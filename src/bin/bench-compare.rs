@@ -0,0 +1,94 @@
+use std::path::Path;
+
+use clap::Parser;
+use twitcher::{
+    samples::{compare, read_commit_samples},
+    stats::find_commit_dir,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Compare the raw benchmark samples of two commits", long_about = None)]
+struct Cli {
+    /// Base commit, or a prefix of its hash
+    base: String,
+
+    /// Commit to compare against the base, or a prefix of its hash
+    other: String,
+
+    /// Only compare benchmarks whose id contains this
+    #[arg(short, long, default_value = "")]
+    filter: String,
+
+    /// Significance level for the Mann-Whitney U test
+    #[arg(short, long, default_value_t = 0.05)]
+    alpha: f64,
+
+    /// Also show changes that are not significant
+    #[arg(long)]
+    all: bool,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let results = Path::new(&cli.results);
+    let Some(base_dir) = find_commit_dir(results, &cli.base) else {
+        eprintln!("no results found for {}", cli.base);
+        std::process::exit(1);
+    };
+    let Some(other_dir) = find_commit_dir(results, &cli.other) else {
+        eprintln!("no results found for {}", cli.other);
+        std::process::exit(1);
+    };
+
+    let base = read_commit_samples(&base_dir);
+    let other = read_commit_samples(&other_dir);
+    if base.is_empty() || other.is_empty() {
+        eprintln!("no benchmark samples archived for one of the commits");
+        std::process::exit(1);
+    }
+
+    let mut comparisons = base
+        .iter()
+        .filter(|(id, _)| id.contains(&cli.filter))
+        .filter_map(|(id, base)| {
+            let other = other.get(id)?;
+            let (Some(base), Some(other)) = (base.per_iteration(), other.per_iteration()) else {
+                eprintln!(
+                    "skipping {id}: unsupported unit {} or {}",
+                    base.unit, other.unit
+                );
+                return None;
+            };
+            let comparison = compare(&base, &other)?;
+            Some((id, comparison))
+        })
+        .filter(|(_, comparison)| cli.all || comparison.p_value < cli.alpha)
+        .collect::<Vec<_>>();
+    comparisons.sort_by(|a, b| b.1.change.abs().total_cmp(&a.1.change.abs()));
+
+    println!(
+        "{:<80} {:>14} {:>14} {:>9} {:>9} {:>7}",
+        "benchmark", "base (ns)", "other (ns)", "change", "p-value", "effect"
+    );
+    for (id, comparison) in comparisons {
+        println!(
+            "{:<80} {:>14.2} {:>14.2} {:>+8.2}% {:>9.4} {:>+7.2}{}",
+            id,
+            comparison.base_median,
+            comparison.other_median,
+            comparison.change * 100.0,
+            comparison.p_value,
+            comparison.effect_size,
+            if comparison.p_value < cli.alpha {
+                " *"
+            } else {
+                ""
+            }
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use git2::{Repository, Sort};
+use regex::Regex;
+use twitcher::{stats::Query, store};
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Find the commit that changed a metric, queueing the commits to measure",
+    long_about = "Find the commit that changed a metric, queueing the commits to measure.\n\n\
+        Run it again once the queued commit has been measured to keep narrowing, until the \
+        culprit is found."
+)]
+struct Cli {
+    /// Metric that changed, like `stress-test-fps.many_cubes.params-benchmark.mean`
+    metric: String,
+
+    /// Last commit known to be good, or a prefix of its hash
+    good: String,
+
+    /// First commit known to be bad, or a prefix of its hash
+    bad: String,
+
+    /// Checkout of the queue branch, where the commit to measure is added
+    #[arg(short, long, default_value = "queue")]
+    queue: String,
+
+    /// Checkout of Bevy
+    #[arg(long, default_value = "bevy")]
+    bevy: String,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+struct Candidate {
+    id: String,
+    summary: String,
+    value: Option<f64>,
+    /// Whether the commit has results, even without a value for the metric
+    measured: bool,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let repo = match Repository::open(&cli.bevy) {
+        Ok(repo) => repo,
+        Err(e) => panic!("failed to open: {}", e),
+    };
+    let resolve = |commit: &str| match repo
+        .revparse_single(commit)
+        .and_then(|o| o.peel_to_commit())
+    {
+        Ok(commit) => commit.id(),
+        Err(error) => {
+            eprintln!("unknown commit {commit}: {error}");
+            std::process::exit(1);
+        }
+    };
+    let good = resolve(&cli.good);
+    let bad = resolve(&cli.bad);
+
+    let stats = store::load(
+        Path::new(&cli.results),
+        &Query::default().metric_prefix(&cli.metric),
+    );
+    let value_of = |commit: &str| {
+        stats
+            .iter()
+            .find(|stat| stat.commit == commit)
+            .and_then(|stat| stat.metrics.get(&cli.metric))
+            .map(|value| value.value)
+    };
+
+    // Merges are measured as a whole, so only follow the first parents
+    let mut revwalk = repo.revwalk().unwrap();
+    revwalk.set_sorting(Sort::TOPOLOGICAL).unwrap();
+    revwalk.simplify_first_parent().unwrap();
+    revwalk.push(bad).unwrap();
+    revwalk.hide(good).unwrap();
+    let mut range = revwalk
+        .filter_map(|id| repo.find_commit(id.ok()?).ok())
+        .map(|commit| Candidate {
+            id: commit.id().to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            value: value_of(&commit.id().to_string()),
+            measured: stats
+                .iter()
+                .any(|stat| stat.commit == commit.id().to_string()),
+        })
+        .collect::<Vec<_>>();
+    range.reverse();
+    // The walk stops at `good` even if it's on a side branch, and goes through the whole
+    // history then
+    let follows_good = range
+        .first()
+        .and_then(|first| repo.find_commit(first.id.parse().ok()?).ok())
+        .and_then(|first| first.parent_id(0).ok())
+        .is_some_and(|parent| parent == good);
+    if !follows_good {
+        eprintln!("{} is not a first parent ancestor of {}", cli.good, cli.bad);
+        std::process::exit(1);
+    }
+
+    let Some(good_value) = value_of(&good.to_string()) else {
+        eprintln!("{} has no value for {}", cli.good, cli.metric);
+        std::process::exit(1);
+    };
+    let Some(bad_value) = range.last().and_then(|last| last.value) else {
+        eprintln!("{} has no value for {}", cli.bad, cli.metric);
+        std::process::exit(1);
+    };
+    // A commit is bad when its value is closer to the bad one than to the good one
+    let is_bad = |value: f64| (value - bad_value).abs() < (value - good_value).abs();
+
+    // Indices in the range, with the good commit just before it
+    let mut last_good: isize = -1;
+    let mut first_bad = range.len() as isize - 1;
+    for (i, candidate) in range.iter().enumerate() {
+        let Some(value) = candidate.value else {
+            continue;
+        };
+        let regressed = is_bad(value);
+        println!(
+            "{} {:>12.3} {}  {}",
+            &candidate.id[..10],
+            value,
+            if regressed { "bad " } else { "good" },
+            candidate.summary
+        );
+        if regressed && (i as isize) < first_bad {
+            first_bad = i as isize;
+        }
+    }
+    for (i, candidate) in range.iter().enumerate().take(first_bad as usize) {
+        if candidate.value.is_some_and(|value| !is_bad(value)) {
+            last_good = i as isize;
+        }
+    }
+
+    if first_bad - last_good == 1 {
+        let culprit = &range[first_bad as usize];
+        let summary_regex = Regex::new("(.*) \\(#([0-9]+)\\)").unwrap();
+        println!();
+        println!("culprit: {} {}", culprit.id, culprit.summary);
+        if let Some(captures) = summary_regex.captures(&culprit.summary) {
+            println!(
+                "https://github.com/bevyengine/bevy/pull/{}",
+                captures.get(2).unwrap().as_str()
+            );
+        }
+        return;
+    }
+
+    let remaining = (first_bad - last_good - 1) as usize;
+    // Commits that were measured without a value, because the metric failed or didn't exist
+    // yet, would never get one by being queued again, so the closest one to the middle that
+    // wasn't measured is used instead
+    let middle = (last_good + first_bad) / 2;
+    let Some(midpoint) = ((last_good + 1)..first_bad)
+        .filter(|&i| !range[i as usize].measured)
+        .min_by_key(|&i| (i - middle).abs())
+        .map(|i| &range[i as usize])
+    else {
+        println!();
+        println!(
+            "no value for {} on the commits left, the culprit is one of:",
+            cli.metric
+        );
+        for candidate in &range[(last_good + 1) as usize..=first_bad as usize] {
+            println!("{} {}", candidate.id, candidate.summary);
+        }
+        std::process::exit(1);
+    };
+    let queued = PathBuf::from(&cli.queue).join(&midpoint.id);
+    println!();
+    if queued.exists() {
+        println!(
+            "{remaining} commits left, waiting for the results of {}",
+            midpoint.id
+        );
+        return;
+    }
+    std::fs::write(&queued, &cli.metric).unwrap();
+    println!(
+        "{remaining} commits left, about {} more steps",
+        (remaining as f64).log2().ceil().max(1.0)
+    );
+    println!(
+        "queued {} {}, push the queue branch to measure it",
+        midpoint.id, midpoint.summary
+    );
+}
@@ -10,8 +10,9 @@ use clap::{Parser, Subcommand};
 use strum::{EnumIter, IntoEnumIterator};
 use twitcher::{
     Metrics,
+    metric_key::MetricKey,
     metrics::*,
-    stats::{Host, Rust, Stats},
+    stats::{Host, Rust, Stats, Unit, Value},
 };
 use xshell::{Shell, cmd};
 
@@ -49,6 +50,8 @@ enum Commands {
         example: String,
     },
     CrateCompileTime,
+    /// Clean and incremental documentation builds, not part of `all`
+    DocBuild,
     StressTest {
         #[arg(short, long)]
         stress_test: String,
@@ -65,8 +68,42 @@ enum Commands {
         #[arg(short, long)]
         nb_frames: u32,
     },
-    Benchmarks,
+    Benchmarks {
+        /// Bench targets to run, all of them if omitted
+        #[arg(short, long)]
+        bench: Vec<String>,
+        /// Only run benchmarks whose criterion id matches one of these glob patterns
+        #[arg(short, long)]
+        include: Vec<String>,
+        /// Skip benchmarks whose criterion id matches one of these glob patterns
+        #[arg(short = 'x', long)]
+        exclude: Vec<String>,
+        /// Don't run `cargo clean` before a full run
+        #[arg(long)]
+        no_clean: bool,
+    },
+    /// Instruction counts of benchmarks under callgrind, not part of `all`
+    Callgrind {
+        /// Bench targets to run, all of them if omitted
+        #[arg(short, long)]
+        bench: Vec<String>,
+        /// Only run benchmarks whose id contains this
+        #[arg(short, long, default_value = "")]
+        filter: String,
+    },
+    /// The whole test suite, not part of `all`
+    TestSuite,
+    Dependencies,
+    /// Clippy diagnostics after a clean, not part of `all`
+    Diagnostics,
+    /// Every example of Bevy, not part of `all`
+    ExampleBuilds,
     LlvmLines,
+    /// Only what measures this metric, like `stress-test-fps.many_cubes.params-benchmark.mean`.
+    /// Used when bisecting a change
+    Metric {
+        key: String,
+    },
     All,
 }
 
@@ -89,158 +126,19 @@ impl Commands {
             Commands::CrateCompileTime => {
                 vec![Box::new(crate_compile_time::CrateCompileTime::on(16))]
             }
+            Commands::DocBuild => {
+                vec![Box::new(doc_build::DocBuild)]
+            }
             Commands::StressTest {
                 stress_test,
                 parameters,
                 nb_frames,
             } => {
                 if stress_test.is_empty() {
-                    vec![
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark".to_string(),
-                            vec![
-                                ("waves".to_string(), Some("60".to_string())),
-                                ("per-wave".to_string(), Some("500".to_string())),
-                                ("benchmark".to_string(), None),
-                                ("mode".to_string(), Some("sprite".to_string())),
-                            ],
-                            10000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark".to_string(),
-                            vec![
-                                ("waves".to_string(), Some("60".to_string())),
-                                ("per-wave".to_string(), Some("500".to_string())),
-                                ("benchmark".to_string(), None),
-                                ("mode".to_string(), Some("mesh2d".to_string())),
-                            ],
-                            5000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark".to_string(),
-                            vec![
-                                ("waves".to_string(), Some("60".to_string())),
-                                ("per-wave".to_string(), Some("500".to_string())),
-                                ("benchmark".to_string(), None),
-                                ("mode".to_string(), Some("sprite_mesh".to_string())),
-                                ("alpha-mode".to_string(), Some("alpha_mask".to_string())),
-                            ],
-                            5000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark".to_string(),
-                            vec![
-                                ("waves".to_string(), Some("60".to_string())),
-                                ("per-wave".to_string(), Some("500".to_string())),
-                                ("benchmark".to_string(), None),
-                                ("mode".to_string(), Some("sprite_mesh".to_string())),
-                                ("alpha-mode".to_string(), Some("blend".to_string())),
-                            ],
-                            5000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_animated_sprites".to_string(),
-                            vec![],
-                            30000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_buttons".to_string(),
-                            vec![],
-                            5000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_cubes".to_string(),
-                            vec![("benchmark".to_string(), None)],
-                            15000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_foxes".to_string(),
-                            vec![],
-                            15000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_morph_targets".to_string(),
-                            vec![],
-                            15000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_gizmos".to_string(),
-                            vec![],
-                            5000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_glyphs".to_string(),
-                            vec![],
-                            10000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_gradients".to_string(),
-                            vec![],
-                            20000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_lights".to_string(),
-                            vec![],
-                            5000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_materials".to_string(),
-                            vec![],
-                            20000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_sprites".to_string(),
-                            vec![],
-                            30000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "many_text2d".to_string(),
-                            vec![],
-                            20000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark_3d".to_string(),
-                            vec![
-                                ("benchmark".to_string(), None),
-                                ("waves".to_string(), Some("100".to_string())),
-                                ("per-wave".to_string(), Some("200".to_string())),
-                                ("alpha-mode".to_string(), Some("blend".to_string())),
-                            ],
-                            10000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark_3d".to_string(),
-                            vec![
-                                ("benchmark".to_string(), None),
-                                ("waves".to_string(), Some("100".to_string())),
-                                ("per-wave".to_string(), Some("500".to_string())),
-                                ("alpha-mode".to_string(), Some("opaque".to_string())),
-                            ],
-                            10000,
-                        )),
-                        Box::new(stress_tests::StressTest::on(
-                            "bevymark_3d".to_string(),
-                            vec![
-                                ("benchmark".to_string(), None),
-                                ("waves".to_string(), Some("100".to_string())),
-                                ("per-wave".to_string(), Some("500".to_string())),
-                                ("alpha-mode".to_string(), Some("alpha_mask".to_string())),
-                            ],
-                            10000,
-                        )),
-                        Box::new(
-                            stress_tests::StressTest::on(
-                                "solari".to_string(),
-                                vec![("many-lights".to_string(), None)],
-                                5000,
-                            )
-                            .with_features(vec![
-                                "https",
-                                "free_camera",
-                                "bevy_solari",
-                            ]),
-                        ),
-                    ]
+                    default_stress_tests()
+                        .into_iter()
+                        .map(|test| Box::new(test) as Box<dyn Metrics>)
+                        .collect()
                 } else {
                     let parameters: Vec<String> =
                         parameters.split(' ').map(|s| s.to_string()).collect();
@@ -272,31 +170,10 @@ impl Commands {
                 nb_frames,
             } => {
                 if scene.is_empty() {
-                    vec![
-                        Box::new(
-                            large_scenes::LargeScene::on(
-                                "bistro".to_string(),
-                                vec![("compress".to_string(), None)],
-                                25000,
-                            )
-                            .with_features(vec!["mipmap_generator/compress"]),
-                        ),
-                        Box::new(large_scenes::LargeScene::on(
-                            "bistro".to_string(),
-                            vec![("no-mip-generation".to_string(), None)],
-                            25000,
-                        )),
-                        Box::new(large_scenes::LargeScene::on(
-                            "caldera_hotel".to_string(),
-                            vec![],
-                            25000,
-                        )),
-                        Box::new(large_scenes::LargeScene::on(
-                            "bevy_city".to_string(),
-                            vec![("no-cpu-culling".into(), None)],
-                            25000,
-                        )),
-                    ]
+                    default_large_scenes()
+                        .into_iter()
+                        .map(|scene| Box::new(scene) as Box<dyn Metrics>)
+                        .collect()
                 } else {
                     let parameters: Vec<String> =
                         parameters.split(' ').map(|s| s.to_string()).collect();
@@ -321,17 +198,50 @@ impl Commands {
                     )]
                 }
             }
-            Commands::Benchmarks => {
-                vec![Box::new(benchmarks::Benchmarks)]
+            Commands::Benchmarks {
+                bench,
+                include,
+                exclude,
+                no_clean,
+            } => {
+                vec![Box::new(benchmarks::Benchmarks::on(
+                    bench, include, exclude, no_clean,
+                ))]
+            }
+            Commands::Callgrind { bench, filter } => {
+                vec![Box::new(callgrind::Callgrind::on(bench, filter))]
+            }
+            Commands::TestSuite => {
+                vec![Box::new(test_suite::TestSuite)]
+            }
+            Commands::Dependencies => {
+                vec![Box::new(dependencies::Dependencies)]
+            }
+            Commands::Diagnostics => {
+                vec![Box::new(diagnostics::Diagnostics)]
+            }
+            Commands::ExampleBuilds => {
+                vec![Box::new(example_builds::ExampleBuilds)]
             }
             Commands::LlvmLines => {
                 vec![Box::new(llvm_lines::LlvmLines)]
             }
+            Commands::Metric { key } => match key.parse() {
+                Ok(key) => metrics_for(&key),
+                Err(_) => vec![],
+            },
             Commands::All => {
                 if recur {
                     Commands::iter()
                         .filter(|c| !matches!(c, Commands::LlvmLines))
                         .filter(|c| !matches!(c, Commands::CrateCompileTime))
+                        .filter(|c| !matches!(c, Commands::Callgrind { .. }))
+                        .filter(|c| !matches!(c, Commands::Metric { .. }))
+                        // Too slow to run on every commit, see `catchup-slow-metrics.sh`
+                        .filter(|c| !matches!(c, Commands::Diagnostics))
+                        .filter(|c| !matches!(c, Commands::TestSuite))
+                        .filter(|c| !matches!(c, Commands::ExampleBuilds))
+                        .filter(|c| !matches!(c, Commands::DocBuild))
                         .flat_map(|command| command.to_metrics(false))
                         .collect()
                 } else {
@@ -342,6 +252,181 @@ impl Commands {
     }
 }
 
+/// The collectors producing `key`.
+fn metrics_for(key: &MetricKey) -> Vec<Box<dyn Metrics>> {
+    let subject = key.subject.clone().unwrap_or_default();
+    let variant = key.variant.clone().unwrap_or_default();
+    // Build metrics end with the number of jobs, like `unix-x86_64-16`
+    let nb_jobs = variant
+        .rsplit('-')
+        .next()
+        .and_then(|jobs| jobs.parse().ok())
+        .unwrap_or(16);
+    match key.family.as_str() {
+        "stress-test-fps" => default_stress_tests()
+            .into_iter()
+            .filter(|test| test.stress_test == subject && test.params() == variant)
+            .map(|test| Box::new(test) as Box<dyn Metrics>)
+            .collect(),
+        "large-scene-fps" => default_large_scenes()
+            .into_iter()
+            .filter(|scene| scene.scene == subject && scene.params() == variant)
+            .map(|scene| Box::new(scene) as Box<dyn Metrics>)
+            .collect(),
+        "compile-time" => vec![Box::new(compile_time::CompileTime::on(
+            "breakout".to_string(),
+            nb_jobs,
+        ))],
+        "crate-compile-time" => vec![Box::new(crate_compile_time::CrateCompileTime::on(nb_jobs))],
+        "native" => vec![Box::new(binary_size::BinarySize::on(
+            "breakout".to_string(),
+        ))],
+        "wasm32-unknown-unknown" => vec![Box::new(wasm_binary_size::WasmBinarySize::on(
+            "breakout".to_string(),
+        ))],
+        "doc-build-time" | "doc" => vec![Box::new(doc_build::DocBuild)],
+        // Instruction counts come from callgrind
+        "benchmarks" if key.unit() == Unit::Count => {
+            vec![Box::new(callgrind::Callgrind::on(vec![], subject))]
+        }
+        "benchmarks" => vec![Box::new(benchmarks::Benchmarks::on(
+            vec![],
+            vec![subject],
+            vec![],
+            false,
+        ))],
+        "tests" => vec![Box::new(test_suite::TestSuite)],
+        "dependencies" => vec![Box::new(dependencies::Dependencies)],
+        "diagnostics" => vec![Box::new(diagnostics::Diagnostics)],
+        "examples" => vec![Box::new(example_builds::ExampleBuilds)],
+        "llvm-lines" => vec![Box::new(llvm_lines::LlvmLines)],
+        _ => vec![],
+    }
+}
+
+/// Stress tests run when no specific one is asked for.
+fn default_stress_tests() -> Vec<stress_tests::StressTest> {
+    vec![
+        stress_tests::StressTest::on(
+            "bevymark".to_string(),
+            vec![
+                ("waves".to_string(), Some("60".to_string())),
+                ("per-wave".to_string(), Some("500".to_string())),
+                ("benchmark".to_string(), None),
+                ("mode".to_string(), Some("sprite".to_string())),
+            ],
+            10000,
+        ),
+        stress_tests::StressTest::on(
+            "bevymark".to_string(),
+            vec![
+                ("waves".to_string(), Some("60".to_string())),
+                ("per-wave".to_string(), Some("500".to_string())),
+                ("benchmark".to_string(), None),
+                ("mode".to_string(), Some("mesh2d".to_string())),
+            ],
+            5000,
+        ),
+        stress_tests::StressTest::on(
+            "bevymark".to_string(),
+            vec![
+                ("waves".to_string(), Some("60".to_string())),
+                ("per-wave".to_string(), Some("500".to_string())),
+                ("benchmark".to_string(), None),
+                ("mode".to_string(), Some("sprite_mesh".to_string())),
+                ("alpha-mode".to_string(), Some("alpha_mask".to_string())),
+            ],
+            5000,
+        ),
+        stress_tests::StressTest::on(
+            "bevymark".to_string(),
+            vec![
+                ("waves".to_string(), Some("60".to_string())),
+                ("per-wave".to_string(), Some("500".to_string())),
+                ("benchmark".to_string(), None),
+                ("mode".to_string(), Some("sprite_mesh".to_string())),
+                ("alpha-mode".to_string(), Some("blend".to_string())),
+            ],
+            5000,
+        ),
+        stress_tests::StressTest::on("many_animated_sprites".to_string(), vec![], 30000),
+        stress_tests::StressTest::on("many_buttons".to_string(), vec![], 5000),
+        stress_tests::StressTest::on(
+            "many_cubes".to_string(),
+            vec![("benchmark".to_string(), None)],
+            15000,
+        ),
+        stress_tests::StressTest::on("many_foxes".to_string(), vec![], 15000),
+        stress_tests::StressTest::on("many_morph_targets".to_string(), vec![], 15000),
+        stress_tests::StressTest::on("many_gizmos".to_string(), vec![], 5000),
+        stress_tests::StressTest::on("many_glyphs".to_string(), vec![], 10000),
+        stress_tests::StressTest::on("many_gradients".to_string(), vec![], 20000),
+        stress_tests::StressTest::on("many_lights".to_string(), vec![], 5000),
+        stress_tests::StressTest::on("many_materials".to_string(), vec![], 20000),
+        stress_tests::StressTest::on("many_sprites".to_string(), vec![], 30000),
+        stress_tests::StressTest::on("many_text2d".to_string(), vec![], 20000),
+        stress_tests::StressTest::on(
+            "bevymark_3d".to_string(),
+            vec![
+                ("benchmark".to_string(), None),
+                ("waves".to_string(), Some("100".to_string())),
+                ("per-wave".to_string(), Some("200".to_string())),
+                ("alpha-mode".to_string(), Some("blend".to_string())),
+            ],
+            10000,
+        ),
+        stress_tests::StressTest::on(
+            "bevymark_3d".to_string(),
+            vec![
+                ("benchmark".to_string(), None),
+                ("waves".to_string(), Some("100".to_string())),
+                ("per-wave".to_string(), Some("500".to_string())),
+                ("alpha-mode".to_string(), Some("opaque".to_string())),
+            ],
+            10000,
+        ),
+        stress_tests::StressTest::on(
+            "bevymark_3d".to_string(),
+            vec![
+                ("benchmark".to_string(), None),
+                ("waves".to_string(), Some("100".to_string())),
+                ("per-wave".to_string(), Some("500".to_string())),
+                ("alpha-mode".to_string(), Some("alpha_mask".to_string())),
+            ],
+            10000,
+        ),
+        stress_tests::StressTest::on(
+            "solari".to_string(),
+            vec![("many-lights".to_string(), None)],
+            5000,
+        )
+        .with_features(vec!["https", "free_camera", "bevy_solari"]),
+    ]
+}
+
+/// Large scenes run when no specific one is asked for.
+fn default_large_scenes() -> Vec<large_scenes::LargeScene> {
+    vec![
+        large_scenes::LargeScene::on(
+            "bistro".to_string(),
+            vec![("compress".to_string(), None)],
+            25000,
+        )
+        .with_features(vec!["mipmap_generator/compress"]),
+        large_scenes::LargeScene::on(
+            "bistro".to_string(),
+            vec![("no-mip-generation".to_string(), None)],
+            25000,
+        ),
+        large_scenes::LargeScene::on("caldera_hotel".to_string(), vec![], 25000),
+        large_scenes::LargeScene::on(
+            "bevy_city".to_string(),
+            vec![("no-cpu-culling".into(), None)],
+            25000,
+        ),
+    ]
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -366,6 +451,14 @@ fn main() {
         String::from_utf8(output).unwrap().parse::<u128>().unwrap() * 1000
     };
 
+    // Partial runs only make sense on top of the existing results
+    let merge_results = cli.merge_results
+        || matches!(&cli.command, Commands::Metric { .. })
+        || matches!(&cli.command, Commands::Benchmarks { bench, include, exclude, .. }
+            if !bench.is_empty() || !include.is_empty() || !exclude.is_empty());
+    // Anything but `all` leaves the commit partial, unless it adds to the results of a full run
+    let mut partial = !matches!(&cli.command, Commands::All);
+
     let metrics_to_run = cli.command.to_metrics(true);
 
     let output_prefix = Path::new(&cli.out)
@@ -373,7 +466,7 @@ fn main() {
         .join(commit.chars().nth(1).unwrap().to_string())
         .join(&commit);
 
-    let mut metrics: HashMap<String, u64> = metrics_to_run
+    let mut metrics: HashMap<String, Value> = metrics_to_run
         .iter()
         .filter(|m| m.prepare())
         .flat_map(|m| {
@@ -411,15 +504,14 @@ fn main() {
         .trim()
         .to_string();
 
-    if cli.merge_results
-        && let Ok(file) = File::open(output_prefix.join("stats.json"))
-    {
+    if merge_results && let Ok(file) = File::open(output_prefix.join("stats.json")) {
         let previous_stats: Result<Stats, _> = serde_json::from_reader(file);
         if let Ok(mut previous_stats) = previous_stats {
             for (key, value) in metrics {
                 previous_stats.metrics.insert(key, value);
             }
             metrics = previous_stats.metrics;
+            partial &= previous_stats.partial;
         }
     }
 
@@ -440,6 +532,7 @@ fn main() {
                 hostname,
                 os_version,
             },
+            partial,
         },
     )
     .unwrap();
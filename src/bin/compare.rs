@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use twitcher::{
+    compare::{Row, category_filter, compare, significance_thresholds},
+    stats::{Query, Stats, find_commit_dir, read_stats},
+    store,
+};
+
+/// Number of measured commits before the base used to estimate the thresholds, like on the
+/// compare page.
+const PRECEDING_COMMITS: usize = 30;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Compare all the metrics of two commits", long_about = None)]
+struct Cli {
+    /// Base commit, or a prefix of its hash
+    base: String,
+
+    /// Commit to compare against the base, or a prefix of its hash
+    other: String,
+
+    /// Only show changes larger than this, in percent, instead of the significant ones
+    #[arg(short, long)]
+    threshold: Option<f64>,
+
+    /// Only compare metrics of this family or filter of the compare page, like `benchmarks`,
+    /// `crate-compile-time` or `stress-tests`. Can be repeated
+    #[arg(long = "family")]
+    families: Vec<String>,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Markdown)]
+    format: Format,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Format {
+    Markdown,
+    Json,
+}
+
+#[derive(Serialize)]
+struct Comparison<'a> {
+    base: &'a str,
+    other: &'a str,
+    changes: Vec<&'a Row>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let results = Path::new(&cli.results);
+    let load = |commit: &str| {
+        let Some(dir) = find_commit_dir(results, commit) else {
+            eprintln!("no results found for {commit}");
+            std::process::exit(1);
+        };
+        match read_stats(&dir.join("stats.json")) {
+            Ok(stats) => stats,
+            Err(error) => {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        }
+    };
+    let base = load(&cli.base);
+    let other = load(&cli.other);
+
+    let preceding = store::load(
+        results,
+        &Query {
+            until: Some(base.commit_timestamp),
+            ..Default::default()
+        },
+    )
+    .into_iter()
+    .filter(|stats| stats.commit != base.commit)
+    .collect::<Vec<Stats>>();
+    let preceding = &preceding[preceding.len().saturating_sub(PRECEDING_COMMITS)..];
+    let thresholds = significance_thresholds(&base, preceding);
+
+    let rows = compare(&base, &other, &thresholds);
+    let mut changes = rows
+        .iter()
+        .filter(|row| {
+            let family = row.metric.split('.').next().unwrap_or_default();
+            cli.families.is_empty()
+                || cli.families.iter().any(|filter| {
+                    family.starts_with(filter.as_str()) || category_filter(&row.metric) == filter
+                })
+        })
+        .filter(|row| match (cli.threshold, row.change) {
+            (_, None) => false,
+            (Some(threshold), Some(change)) => change.abs() >= threshold,
+            (None, Some(_)) => row.significant,
+        })
+        .collect::<Vec<_>>();
+    changes.sort_by(|a, b| {
+        b.change
+            .unwrap_or_default()
+            .abs()
+            .total_cmp(&a.change.unwrap_or_default().abs())
+    });
+
+    let comparison = Comparison {
+        base: &base.commit,
+        other: &other.commit,
+        changes,
+    };
+    match cli.format {
+        Format::Markdown => print!("{}", markdown(&comparison)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&comparison).unwrap()),
+    }
+}
+
+fn markdown(comparison: &Comparison) -> String {
+    let base = &comparison.base[..8];
+    let other = &comparison.other[..8];
+    let mut out = format!(
+        "| Metric | Base ({base}) | Other ({other}) | Change | Threshold |\n\
+         |---|---:|---:|---:|---:|\n"
+    );
+    for row in &comparison.changes {
+        let format = |value: Option<f64>| {
+            value
+                .map(|value| row.unit.format(value))
+                .unwrap_or("-".to_string())
+        };
+        let status = match (row.significant, row.better) {
+            (false, _) => "",
+            (true, true) => " (better)",
+            (true, false) => " (worse)",
+        };
+        out.push_str(&format!(
+            "| `{}` | {} | {} | {:+.2}%{status} | {} |\n",
+            row.metric.replace('|', "\\|"),
+            format(row.base),
+            format(row.other),
+            row.change.unwrap_or_default(),
+            row.threshold
+                .map(|threshold| format!("±{threshold:.2}%"))
+                .unwrap_or("-".to_string()),
+        ));
+    }
+    if comparison.changes.is_empty() {
+        out.push_str("| No changes | | | | |\n");
+    }
+    out
+}
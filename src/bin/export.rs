@@ -0,0 +1,255 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use twitcher::{
+    metric_key::MetricKey,
+    stats::{Query, Stats},
+    store,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Export the results as tables", long_about = None)]
+struct Cli {
+    #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+
+    /// Folder where the tables are written
+    #[arg(short, long, default_value = "export")]
+    out: String,
+
+    /// Only export metrics whose name starts with this
+    #[arg(short, long)]
+    prefix: Option<String>,
+
+    /// Only export commits made on or after this date, like `2025-06-01`
+    #[arg(long, value_parser = parse_date)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Only export commits made before this date, like `2025-07-01`
+    #[arg(long, value_parser = parse_date)]
+    until: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Also write a table per family, with a row per commit and a column per metric
+    #[arg(short, long)]
+    wide: bool,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Format {
+    Csv,
+    Jsonl,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+        }
+    }
+}
+
+fn parse_date(date: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(date) {
+        return Ok(date.to_utc());
+    }
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|error| format!("invalid date {date}: {error}"))
+}
+
+fn date(millis: u128) -> String {
+    chrono::DateTime::from_timestamp_millis(millis as i64)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// A row of the long table, one per metric of each commit.
+#[derive(Serialize)]
+struct Row<'a> {
+    commit: &'a str,
+    commit_timestamp: String,
+    timestamp: String,
+    hostname: &'a str,
+    os_version: &'a str,
+    rust_stable: &'a str,
+    rust_nightly: &'a str,
+    metric: &'a str,
+    value: f64,
+    unit: String,
+    lower: Option<f64>,
+    upper: Option<f64>,
+}
+
+/// Writes rows as CSV or as JSON Lines.
+enum TableWriter {
+    Csv(Box<csv::Writer<File>>),
+    Jsonl(BufWriter<File>),
+}
+
+impl TableWriter {
+    fn create(path: &Path, format: Format) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(match format {
+            Format::Csv => TableWriter::Csv(Box::new(csv::Writer::from_writer(file))),
+            Format::Jsonl => TableWriter::Jsonl(BufWriter::new(file)),
+        })
+    }
+
+    fn write(&mut self, row: &impl Serialize) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            TableWriter::Csv(writer) => writer.serialize(row)?,
+            TableWriter::Jsonl(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a row whose columns are only known at runtime, the header of CSV tables being
+    /// written with the first row.
+    fn write_columns(
+        &mut self,
+        columns: &[&str],
+        values: Vec<serde_json::Value>,
+        first: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            TableWriter::Csv(writer) => {
+                if first {
+                    writer.write_record(columns)?;
+                }
+                writer.write_record(values.iter().map(|value| match value {
+                    serde_json::Value::Null => String::new(),
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                }))?;
+            }
+            TableWriter::Jsonl(_) => {
+                let row = columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .zip(values)
+                    .collect::<serde_json::Map<_, _>>();
+                self.write(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            TableWriter::Csv(writer) => writer.flush(),
+            TableWriter::Jsonl(writer) => writer.flush(),
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let mut query = Query::default();
+    if let Some(prefix) = &cli.prefix {
+        query = query.metric_prefix(prefix);
+    }
+    if let Some(since) = cli.since {
+        query = query.since(since);
+    }
+    if let Some(until) = cli.until {
+        query = query.until(until - chrono::Duration::milliseconds(1));
+    }
+    let stats = store::load(Path::new(&cli.results), &query);
+
+    let out = PathBuf::from(&cli.out);
+    std::fs::create_dir_all(&out)?;
+
+    let long = out.join(format!("metrics.{}", cli.format.extension()));
+    let mut writer = TableWriter::create(&long, cli.format)?;
+    let mut rows = 0;
+    for stat in &stats {
+        let mut metrics = stat.metrics.iter().collect::<Vec<_>>();
+        metrics.sort_by_key(|(name, _)| *name);
+        for (name, value) in metrics {
+            writer.write(&Row {
+                commit: &stat.commit,
+                commit_timestamp: date(stat.commit_timestamp),
+                timestamp: date(stat.timestamp),
+                hostname: &stat.host.hostname,
+                os_version: &stat.host.os_version,
+                rust_stable: &stat.rust.stable,
+                rust_nightly: &stat.rust.nightly,
+                metric: name,
+                value: value.value,
+                unit: value.unit.to_string(),
+                lower: value.lower,
+                upper: value.upper,
+            })?;
+            rows += 1;
+        }
+    }
+    writer.flush()?;
+    println!("{rows} rows written to {}", long.display());
+
+    if cli.wide {
+        write_wide(&stats, &out, cli.format)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a table per family, with a row per commit and a column per metric of the family.
+fn write_wide(
+    stats: &[Stats],
+    out: &Path,
+    format: Format,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut families: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for stat in stats {
+        for name in stat.metrics.keys() {
+            let family = match name.parse::<MetricKey>() {
+                Ok(key) => key.family,
+                Err(_) => name.split('.').next().unwrap_or_default().to_string(),
+            };
+            families.entry(family).or_default().insert(name);
+        }
+    }
+
+    for (family, metrics) in families {
+        let path = out.join(format!("{}.{}", family, format.extension()));
+        let mut writer = TableWriter::create(&path, format)?;
+        let mut columns = vec!["commit", "commit_timestamp"];
+        columns.extend(metrics.iter());
+
+        let mut first = true;
+        for stat in stats {
+            if !metrics.iter().any(|name| stat.metrics.contains_key(*name)) {
+                continue;
+            }
+            let mut values = vec![
+                stat.commit.clone().into(),
+                date(stat.commit_timestamp).into(),
+            ];
+            values.extend(
+                metrics
+                    .iter()
+                    .map(|name| stat.metrics.get(*name).map(|value| value.value).into()),
+            );
+            writer.write_columns(&columns, values, first)?;
+            first = false;
+        }
+        writer.flush()?;
+        println!("{family} written to {}", path.display());
+    }
+    Ok(())
+}
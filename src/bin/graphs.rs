@@ -1,26 +1,15 @@
-use std::{
-    collections::HashSet,
-    fs::{self, File},
-    io::BufReader,
-    path::Path,
-};
+use std::{collections::HashSet, fs, path::Path};
 
 use plotters::prelude::*;
 use twitcher::{
     file_safe_metric_name,
-    stats::{Stats, find_stats_files},
+    stats::{Query, Stats},
+    store,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     fs::create_dir_all("graphs").unwrap();
-    let stats: Vec<Stats> = find_stats_files(Path::new("results"))
-        .iter()
-        .map(|path| {
-            let file = File::open(path).unwrap();
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap()
-        })
-        .collect();
+    let stats: Vec<Stats> = store::load(Path::new("results"), &Query::default());
 
     let keys: HashSet<_> = stats.iter().flat_map(|stat| stat.metrics.keys()).collect();
     for metric in keys {
@@ -32,15 +21,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         chrono::DateTime::from_timestamp_millis(stat.commit_timestamp as i64)
                             .unwrap(),
                         stat.commit.clone(),
-                        *value,
+                        value.value,
+                        value.unit,
                     )
                 })
             })
             .collect::<Vec<_>>();
 
         data.sort_by_key(|d| d.0);
-        let min = data.iter().min_by_key(|d| d.2).unwrap().2;
-        let max = data.iter().max_by_key(|d| d.2).unwrap().2;
+        let min = data.iter().map(|d| d.2).fold(f64::INFINITY, f64::min);
+        let max = data.iter().map(|d| d.2).fold(f64::NEG_INFINITY, f64::max);
+        let unit = data[0].3;
 
         let out = format!("graphs/{}.svg", file_safe_metric_name(metric));
 
@@ -53,7 +44,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .caption(metric, ("sans-serif", 50.0).into_font())
             .build_cartesian_2d(data.first().unwrap().0..data.last().unwrap().0, min..max)?;
 
-        chart.configure_mesh().light_line_style(WHITE).draw()?;
+        chart
+            .configure_mesh()
+            .light_line_style(WHITE)
+            .y_label_formatter(&|value| unit.format(*value))
+            .draw()?;
 
         chart
             .draw_series(LineSeries::new(data.iter().map(|x| (x.0, x.2)), BLUE))
@@ -1,10 +1,14 @@
-use std::{fs, path::Path};
+use std::{collections::HashMap, fs, path::Path};
 
 use git2::{Repository, Sort};
 use regex::Regex;
 use serde::Serialize;
 use tera::Tera;
-use twitcher::stats::find_stats_files;
+use twitcher::{
+    metric_key::MetricKey,
+    stats::{Query, Stats},
+    store,
+};
 
 #[derive(Serialize)]
 enum Status {
@@ -22,6 +26,7 @@ struct Commit {
     status: Status,
     previous_done: String,
     has_example_run: bool,
+    examples_failed: Option<u64>,
 }
 
 fn main() {
@@ -30,18 +35,15 @@ fn main() {
         Err(e) => panic!("failed to open: {}", e),
     };
 
-    let commits_done: Vec<String> = find_stats_files(Path::new("results"))
-        .iter()
-        .map(|path| {
-            path.parent()
-                .unwrap()
-                .file_name()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string()
-        })
-        .collect();
+    let commits_done: HashMap<String, Stats> = store::load(
+        Path::new("results"),
+        &Query::default().metric_prefix("examples.total."),
+    )
+    .into_iter()
+    // Commits measured while bisecting still need their full run
+    .filter(|stats| !stats.partial)
+    .map(|stats| (stats.commit.clone(), stats))
+    .collect();
     let example_run_commits: Vec<String> = fs::read_dir("example-runs")
         .unwrap()
         .filter_map(|f| f.ok())
@@ -63,6 +65,9 @@ fn main() {
     let mut revwalk = repo.revwalk().unwrap();
     revwalk.set_sorting(Sort::TIME).unwrap();
     revwalk.push_head().unwrap();
+    let examples_failed_key = MetricKey::new("examples", "failed")
+        .with_subject("total")
+        .to_string();
     let mut commits = revwalk
         .filter_map(|c| repo.find_commit(*c.as_ref().unwrap()).ok())
         .take(500)
@@ -70,8 +75,12 @@ fn main() {
             let captures = summary_regex.captures(commit.summary().unwrap())?;
             let id = commit.id().to_string();
             let has_example_run = example_run_commits.contains(&id);
+            let examples_failed = commits_done
+                .get(&id)
+                .and_then(|stats| stats.metrics.get(&examples_failed_key))
+                .map(|failed| failed.value as u64);
             Some(Commit {
-                status: if commits_done.contains(&id) {
+                status: if commits_done.contains_key(&id) {
                     Status::Done
                 } else if commits_queued.contains(&id) {
                     Status::Queued
@@ -83,6 +92,7 @@ fn main() {
                 summary: captures.get(1).unwrap().as_str().to_string(),
                 pr: captures.get(2).unwrap().as_str().parse().unwrap(),
                 has_example_run,
+                examples_failed,
                 previous_done: String::new(),
             })
         })
@@ -0,0 +1,195 @@
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use clap::Parser;
+use twitcher::{
+    metric_key::MetricKey,
+    stats::{Query, Stats, Unit},
+    store,
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Expose the results in the OpenMetrics text format", long_about = None)]
+struct Cli {
+    /// File to write the metrics to
+    #[arg(short, long, default_value = "metrics.txt")]
+    out: String,
+
+    /// Serve the metrics on this address instead of writing them, like `127.0.0.1:9184`
+    #[arg(short, long)]
+    serve: Option<String>,
+
+    /// Expose every measured commit instead of only the latest value of each metric, to
+    /// backfill a Prometheus database
+    #[arg(long)]
+    history: bool,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+/// Samples of a metric family, by their labels.
+struct Family {
+    unit: Unit,
+    series: BTreeMap<String, Vec<(u128, f64)>>,
+}
+
+/// Name of the OpenMetrics family, with the unit as suffix as the format requires.
+fn family_name(key: &MetricKey) -> String {
+    let mut name = format!("twitcher_{}", key.family);
+    if let Some(dimension) = &key.dimension {
+        name.push('_');
+        name.push_str(dimension);
+    }
+    if let Some(suffix) = unit_suffix(key.unit()) {
+        name.push('_');
+        name.push_str(suffix);
+    }
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn unit_suffix(unit: Unit) -> Option<&'static str> {
+    match unit {
+        Unit::Bytes => Some("bytes"),
+        Unit::Nanoseconds | Unit::Milliseconds => Some("seconds"),
+        Unit::Fps => Some("fps"),
+        Unit::Percent => Some("percent"),
+        Unit::Count => None,
+    }
+}
+
+/// Durations are exposed in seconds, the base unit Prometheus expects.
+fn base_value(unit: Unit, value: f64) -> f64 {
+    match unit {
+        Unit::Nanoseconds => value / 1e9,
+        Unit::Milliseconds => value / 1e3,
+        _ => value,
+    }
+}
+
+fn labels(key: &MetricKey, stats: &Stats) -> String {
+    let subject = match key.family.as_str() {
+        "stress-test-fps" => "stress_test",
+        "large-scene-fps" => "scene",
+        "crate-compile-time" => "crate",
+        "benchmarks" => "benchmark",
+        "examples" => "example",
+        "llvm-lines" => "function",
+        _ => "subject",
+    };
+    let variant = if key.is_runtime() {
+        "params"
+    } else {
+        "platform"
+    };
+    let mut labels = vec![];
+    if let Some(value) = &key.subject {
+        labels.push((subject, value.as_str()));
+    }
+    if let Some(value) = &key.variant {
+        labels.push((variant, value.as_str()));
+    }
+    labels.push(("statistic", key.statistic.as_str()));
+    labels.push(("host", stats.host.hostname.as_str()));
+    labels
+        .iter()
+        .map(|(name, value)| format!("{name}=\"{}\"", escape(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn render(stats: &[Stats], history: bool) -> String {
+    let mut families: BTreeMap<String, Family> = BTreeMap::new();
+    // Stats are sorted by commit timestamp, so the latest value of a series comes last
+    for stat in stats {
+        for (name, value) in &stat.metrics {
+            let Ok(key) = name.parse::<MetricKey>() else {
+                continue;
+            };
+            let family = families.entry(family_name(&key)).or_insert_with(|| Family {
+                unit: value.unit,
+                series: BTreeMap::new(),
+            });
+            if family.unit != value.unit {
+                continue;
+            }
+            let samples = family.series.entry(labels(&key, stat)).or_default();
+            if !history {
+                samples.clear();
+            }
+            samples.push((stat.commit_timestamp, base_value(value.unit, value.value)));
+        }
+    }
+
+    let mut out = String::new();
+    for (name, family) in &families {
+        writeln!(out, "# TYPE {name} gauge").unwrap();
+        if let Some(suffix) = unit_suffix(family.unit) {
+            writeln!(out, "# UNIT {name} {suffix}").unwrap();
+        }
+        for (labels, samples) in &family.series {
+            for (timestamp, value) in samples {
+                writeln!(
+                    out,
+                    "{name}{{{labels}}} {value:?} {}.{:03}",
+                    timestamp / 1000,
+                    timestamp % 1000
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    // Which commit the latest values come from
+    if let Some(latest) = stats.last() {
+        writeln!(out, "# TYPE twitcher_commit info").unwrap();
+        writeln!(
+            out,
+            "twitcher_commit_info{{commit=\"{}\",host=\"{}\",rust_stable=\"{}\",rust_nightly=\"{}\"}} 1",
+            escape(&latest.commit),
+            escape(&latest.host.hostname),
+            escape(&latest.rust.stable),
+            escape(&latest.rust.nightly),
+        )
+        .unwrap();
+    }
+    out.push_str("# EOF\n");
+    out
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let results = Path::new(&cli.results);
+
+    let Some(address) = &cli.serve else {
+        let stats = store::load(results, &Query::default());
+        std::fs::write(&cli.out, render(&stats, cli.history)).unwrap();
+        return;
+    };
+
+    let server = tiny_http::Server::http(address).unwrap();
+    println!("serving metrics on http://{address}/metrics");
+    let content_type = tiny_http::Header::from_bytes(
+        "Content-Type",
+        "application/openmetrics-text; version=1.0.0; charset=utf-8",
+    )
+    .unwrap();
+    for request in server.incoming_requests() {
+        // Results are loaded again on each scrape, to pick up new runs
+        let stats = store::load(results, &Query::default());
+        let response = tiny_http::Response::from_string(render(&stats, cli.history))
+            .with_header(content_type.clone());
+        if let Err(error) = request.respond(response) {
+            eprintln!("failed to respond: {error}");
+        }
+    }
+}
@@ -1,9 +1,10 @@
-use std::{collections::HashSet, fs::File, io::BufReader, path::Path};
+use std::{collections::HashSet, path::Path};
 
 use tera::Tera;
 use twitcher::{
     file_safe_metric_name,
-    stats::{Stats, find_stats_files},
+    stats::{Query, Stats},
+    store,
 };
 
 fn main() {
@@ -11,14 +12,7 @@ fn main() {
     // Prepare the context with some data
     let mut context = tera::Context::new();
 
-    let stats: Vec<Stats> = find_stats_files(Path::new("results"))
-        .iter()
-        .map(|path| {
-            let file = File::open(path).unwrap();
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap()
-        })
-        .collect();
+    let stats: Vec<Stats> = store::load(Path::new("results"), &Query::default());
 
     let keys: HashSet<_> = stats
         .iter()
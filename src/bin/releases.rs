@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use git2::{Repository, Sort};
+use regex::Regex;
+use serde::Serialize;
+use tera::Tera;
+use twitcher::{
+    change_points::NoiseModel,
+    metric_key::MetricKey,
+    stats::{Query, Stats},
+    store,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Compare Bevy releases, or any refs, on every metric",
+    long_about = "Compare Bevy releases, or any refs, on every metric.\n\n\
+        Each ref is resolved to its nearest measured ancestor. Refs that were not measured \
+        themselves are added to the queue, so that the comparison gets exact once they are."
+)]
+struct Cli {
+    /// Tags, branches or commits to compare, oldest first. The latest releases if omitted
+    refs: Vec<String>,
+
+    /// Number of releases to compare when no refs are given
+    #[arg(long, default_value_t = 4)]
+    releases: usize,
+
+    /// Name of the generated page and Markdown summary, without extension
+    #[arg(short, long, default_value = "releases")]
+    out: String,
+
+    /// Checkout of the queue branch, where the refs to measure are added
+    #[arg(short, long, default_value = "queue")]
+    queue: String,
+
+    /// Checkout of Bevy
+    #[arg(long, default_value = "bevy")]
+    bevy: String,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+#[derive(Serialize)]
+struct Release {
+    name: String,
+    commit: String,
+    /// Nearest measured ancestor, the commit itself when it was measured
+    measured: Option<String>,
+    /// Number of commits between the ref and its nearest measured ancestor
+    distance: usize,
+    queued: bool,
+}
+
+#[derive(Serialize)]
+struct Family {
+    name: String,
+    rows: Vec<Row>,
+}
+
+#[derive(Serialize)]
+struct Row {
+    metric: String,
+    cells: Vec<Cell>,
+}
+
+#[derive(Serialize, Default)]
+struct Cell {
+    value: Option<String>,
+    /// Relative change from the previous release with a value, in percent
+    change: Option<f64>,
+    /// `better`, `worse` or `neutral` when the change is not significant
+    class: &'static str,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let repo = match Repository::open(&cli.bevy) {
+        Ok(repo) => repo,
+        Err(e) => panic!("failed to open: {}", e),
+    };
+    let refs = if cli.refs.is_empty() {
+        latest_releases(&repo, cli.releases)
+    } else {
+        cli.refs.clone()
+    };
+    if refs.is_empty() {
+        eprintln!("no release tags in {}, fetch them or pass refs", cli.bevy);
+        std::process::exit(1);
+    }
+
+    let stats = store::load(Path::new(&cli.results), &Query::default());
+    let by_commit = stats
+        .iter()
+        .map(|stat| (stat.commit.as_str(), stat))
+        .collect::<HashMap<_, _>>();
+
+    let queue = PathBuf::from(&cli.queue);
+    if !queue.is_dir() {
+        eprintln!(
+            "no queue checkout at {}, missing refs won't be queued",
+            cli.queue
+        );
+    }
+    let releases = refs
+        .iter()
+        .map(|name| {
+            let commit = match repo.revparse_single(name).and_then(|o| o.peel_to_commit()) {
+                Ok(commit) => commit.id(),
+                Err(error) => {
+                    eprintln!("unknown ref {name}: {error}");
+                    std::process::exit(1);
+                }
+            };
+            let mut revwalk = repo.revwalk().unwrap();
+            revwalk.set_sorting(Sort::TIME).unwrap();
+            revwalk.push(commit).unwrap();
+            let nearest = revwalk
+                .flatten()
+                .enumerate()
+                .find(|(_, id)| by_commit.contains_key(id.to_string().as_str()));
+
+            let commit = commit.to_string();
+            let queued = queue.join(&commit);
+            let queued = if nearest.is_some_and(|(distance, _)| distance == 0) {
+                false
+            } else if queued.exists() {
+                true
+            } else if queue.is_dir() {
+                std::fs::write(&queued, "").unwrap();
+                println!("queued {name} ({commit})");
+                true
+            } else {
+                false
+            };
+            Release {
+                name: name.clone(),
+                measured: nearest.map(|(_, id)| id.to_string()),
+                distance: nearest.map(|(distance, _)| distance).unwrap_or_default(),
+                commit,
+                queued,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let measured = releases
+        .iter()
+        .map(|release| {
+            release
+                .measured
+                .as_ref()
+                .map(|commit| by_commit[commit.as_str()])
+        })
+        .collect::<Vec<_>>();
+    let families = compare(&stats, &measured);
+
+    let tera = Tera::new("templates/*").unwrap();
+    let mut context = tera::Context::new();
+    context.insert("releases", &releases);
+    context.insert("families", &families);
+    let rendered = tera.render("releases.html", &context).unwrap();
+    std::fs::write(format!("{}.html", cli.out), &rendered).unwrap();
+    std::fs::write(format!("{}.md", cli.out), markdown(&releases, &families)).unwrap();
+}
+
+/// The latest `vX.Y.0` tags, oldest first.
+fn latest_releases(repo: &Repository, count: usize) -> Vec<String> {
+    let release_regex = Regex::new("^v([0-9]+)\\.([0-9]+)\\.0$").unwrap();
+    let mut tags = repo
+        .tag_names(Some("v*"))
+        .unwrap()
+        .iter()
+        .flatten()
+        .filter_map(|tag| {
+            let captures = release_regex.captures(tag)?;
+            let major: u32 = captures.get(1).unwrap().as_str().parse().ok()?;
+            let minor: u32 = captures.get(2).unwrap().as_str().parse().ok()?;
+            Some(((major, minor), tag.to_string()))
+        })
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags.into_iter()
+        .rev()
+        .take(count)
+        .rev()
+        .map(|(_, tag)| tag)
+        .collect()
+}
+
+/// Values of every tracked metric on each release, with the change from the previous one.
+fn compare(stats: &[Stats], releases: &[Option<&Stats>]) -> Vec<Family> {
+    let mut families: BTreeMap<String, Vec<Row>> = BTreeMap::new();
+    let mut metrics = releases
+        .iter()
+        .flatten()
+        .flat_map(|stat| stat.metrics.keys())
+        .collect::<Vec<_>>();
+    metrics.sort();
+    metrics.dedup();
+
+    for metric in metrics {
+        let Ok(key) = metric.parse::<MetricKey>() else {
+            continue;
+        };
+        if !key.is_tracked() {
+            continue;
+        }
+        let values = releases
+            .iter()
+            .map(|release| release.and_then(|stat| stat.metrics.get(metric)))
+            .collect::<Vec<_>>();
+        if values.iter().flatten().count() < 2 {
+            continue;
+        }
+        let history = stats
+            .iter()
+            .filter_map(|stat| stat.metrics.get(metric))
+            .map(|value| value.value)
+            .collect::<Vec<_>>();
+        let noise = NoiseModel::for_metric(&key, &history);
+
+        let mut previous = None;
+        let cells = values
+            .iter()
+            .map(|value| {
+                let Some(value) = value else {
+                    return Cell::default();
+                };
+                let mut cell = Cell {
+                    value: Some(value.unit.format(value.value)),
+                    ..Default::default()
+                };
+                if let Some(previous) = previous {
+                    let difference = noise.compare(previous, value.value);
+                    cell.change = Some(difference.magnitude * 100.0);
+                    cell.class = if !difference.significant {
+                        "neutral"
+                    } else if (difference.magnitude > 0.0) == key.higher_is_better() {
+                        "better"
+                    } else {
+                        "worse"
+                    };
+                }
+                previous = Some(value.value);
+                cell
+            })
+            .collect();
+        families.entry(key.family).or_default().push(Row {
+            metric: metric.clone(),
+            cells,
+        });
+    }
+
+    families
+        .into_iter()
+        .map(|(name, rows)| Family { name, rows })
+        .collect()
+}
+
+fn markdown(releases: &[Release], families: &[Family]) -> String {
+    let short = |commit: &str| commit.chars().take(10).collect::<String>();
+    let mut out = "## Release comparison\n\n".to_string();
+    for release in releases {
+        out.push_str(&format!(
+            "- {} ({}): ",
+            release.name,
+            short(&release.commit)
+        ));
+        match &release.measured {
+            Some(_) if release.distance == 0 => out.push_str("measured"),
+            Some(measured) => out.push_str(&format!(
+                "nearest measured commit {}, {} commits before",
+                short(measured),
+                release.distance
+            )),
+            None => out.push_str("no measured ancestor"),
+        }
+        if release.queued {
+            out.push_str(", queued");
+        }
+        out.push('\n');
+    }
+
+    for family in families {
+        out.push_str(&format!("\n### {}\n\n| Metric |", family.name));
+        for release in releases {
+            out.push_str(&format!(" {} |", release.name));
+        }
+        out.push_str("\n|---|");
+        out.push_str(&"---:|".repeat(releases.len()));
+        out.push('\n');
+        for row in &family.rows {
+            out.push_str(&format!("| `{}` |", row.metric.replace('|', "\\|")));
+            for cell in &row.cells {
+                match (&cell.value, cell.change) {
+                    (None, _) => out.push_str(" |"),
+                    (Some(value), None) => out.push_str(&format!(" {value} |")),
+                    (Some(value), Some(change)) => {
+                        let status = match cell.class {
+                            "better" => ", improvement",
+                            "worse" => ", regression",
+                            _ => "",
+                        };
+                        out.push_str(&format!(" {value} ({change:+.2}%{status}) |"));
+                    }
+                }
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
@@ -0,0 +1,259 @@
+use std::path::Path;
+
+use clap::Parser;
+use git2::{Oid, Repository, Sort};
+use regex::Regex;
+use serde::Serialize;
+use twitcher::{
+    change_points::{Difference, NoiseModel},
+    metric_key::MetricKey,
+    stats::{Query, Stats, Unit},
+    store,
+};
+
+/// Number of measured commits before the reported one used to estimate the noise of a metric.
+const NOISE_HISTORY: usize = 30;
+
+const SECTIONS: [&str; 5] = [
+    "Compile time",
+    "Binary size",
+    "Stress tests",
+    "Benchmarks",
+    "Other",
+];
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Report the significant changes of a commit", long_about = None)]
+struct Cli {
+    /// Commit to report on, or a prefix of its hash
+    commit: String,
+
+    /// Commit to compare to, the previous measured commit if omitted
+    #[arg(short, long)]
+    base: Option<String>,
+
+    /// Also write the report as JSON to this file
+    #[arg(short, long)]
+    json: Option<String>,
+
+    /// List every compared metric, not only the significant changes
+    #[arg(long)]
+    all: bool,
+
+    /// Checkout of Bevy
+    #[arg(long, default_value = "bevy")]
+    bevy: String,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+#[derive(Serialize)]
+struct Report {
+    commit: String,
+    base: String,
+    summary: Option<String>,
+    pr: Option<u32>,
+    changes: Vec<Change>,
+    unchanged: usize,
+}
+
+#[derive(Serialize)]
+struct Change {
+    section: &'static str,
+    metric: String,
+    unit: Unit,
+    #[serde(flatten)]
+    difference: Difference,
+    regression: bool,
+}
+
+fn section(key: &MetricKey) -> &'static str {
+    match key.family.as_str() {
+        "compile-time" | "crate-compile-time" | "doc-build-time" => SECTIONS[0],
+        "native" => SECTIONS[1],
+        family if family.starts_with("wasm32") => SECTIONS[1],
+        "stress-test-fps" | "large-scene-fps" => SECTIONS[2],
+        "benchmarks" => SECTIONS[3],
+        _ => SECTIONS[4],
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let stats = store::load(Path::new(&cli.results), &Query::default());
+    let find = |commit: &str| {
+        let mut found = stats.iter().filter(|stat| stat.commit.starts_with(commit));
+        match (found.next(), found.next()) {
+            (Some(stat), None) => stat,
+            (None, _) => {
+                eprintln!("no results for {commit}");
+                std::process::exit(1);
+            }
+            (Some(_), Some(_)) => {
+                eprintln!("{commit} is ambiguous");
+                std::process::exit(1);
+            }
+        }
+    };
+    let other = find(&cli.commit);
+
+    let repo = Repository::open(&cli.bevy).ok();
+    let base = match &cli.base {
+        Some(base) => find(base),
+        None => match previous_done(repo.as_ref(), &stats, other) {
+            Some(base) => base,
+            None => {
+                eprintln!("no measured commit before {}", other.commit);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let history = stats
+        .iter()
+        .filter(|stat| stat.commit_timestamp < other.commit_timestamp)
+        .collect::<Vec<_>>();
+    let history = &history[history.len().saturating_sub(NOISE_HISTORY)..];
+
+    let mut changes = vec![];
+    let mut unchanged = 0;
+    for (name, value) in &other.metrics {
+        let Ok(key) = name.parse::<MetricKey>() else {
+            continue;
+        };
+        let Some(before) = base.metrics.get(name) else {
+            continue;
+        };
+        if !key.is_tracked() {
+            continue;
+        }
+        let values = history
+            .iter()
+            .filter_map(|stat| stat.metrics.get(name))
+            .map(|value| value.value)
+            .collect::<Vec<_>>();
+        let difference = NoiseModel::for_metric(&key, &values).compare(before.value, value.value);
+        if !difference.significant && !cli.all {
+            unchanged += 1;
+            continue;
+        }
+        changes.push(Change {
+            section: section(&key),
+            metric: name.clone(),
+            unit: value.unit,
+            regression: difference.significant
+                && (difference.magnitude > 0.0) != key.higher_is_better(),
+            difference,
+        });
+    }
+    changes.sort_by(|a, b| {
+        b.difference
+            .magnitude
+            .abs()
+            .total_cmp(&a.difference.magnitude.abs())
+    });
+
+    let summary_regex = Regex::new("(.*) \\(#([0-9]+)\\)").unwrap();
+    let summary = repo
+        .as_ref()
+        .and_then(|repo| repo.find_commit(Oid::from_str(&other.commit).ok()?).ok())
+        .and_then(|commit| commit.summary().map(|summary| summary.to_string()));
+    let (summary, pr) = match summary
+        .as_deref()
+        .and_then(|summary| summary_regex.captures(summary))
+    {
+        Some(captures) => (
+            Some(captures.get(1).unwrap().as_str().to_string()),
+            captures.get(2).unwrap().as_str().parse().ok(),
+        ),
+        None => (summary, None),
+    };
+
+    let report = Report {
+        commit: other.commit.clone(),
+        base: base.commit.clone(),
+        summary,
+        pr,
+        changes,
+        unchanged,
+    };
+    print!("{}", markdown(&report));
+    if let Some(json) = &cli.json {
+        std::fs::write(json, serde_json::to_string_pretty(&report).unwrap()).unwrap();
+    }
+}
+
+/// The closest older commit in the history of `commit` that has results.
+fn previous_done<'a>(
+    repo: Option<&Repository>,
+    stats: &'a [Stats],
+    commit: &Stats,
+) -> Option<&'a Stats> {
+    let measured = |id: &str| stats.iter().find(|stat| stat.commit == id);
+    if let Some(repo) = repo
+        && let Ok(id) = Oid::from_str(&commit.commit)
+    {
+        let mut revwalk = repo.revwalk().ok()?;
+        revwalk.set_sorting(Sort::TIME).ok()?;
+        if revwalk.push(id).is_ok() {
+            return revwalk
+                .flatten()
+                .skip(1)
+                .find_map(|id| measured(&id.to_string()));
+        }
+    }
+    // Without the history, the latest measured commit made before it
+    stats
+        .iter()
+        .filter(|stat| stat.commit_timestamp < commit.commit_timestamp)
+        .max_by_key(|stat| stat.commit_timestamp)
+}
+
+fn markdown(report: &Report) -> String {
+    let short = |commit: &str| commit.chars().take(10).collect::<String>();
+    let mut out = format!("## Performance changes of {}", short(&report.commit));
+    if let Some(pr) = report.pr {
+        out.push_str(&format!(" (#{pr})"));
+    }
+    out.push_str("\n\n");
+    if let Some(summary) = &report.summary {
+        out.push_str(&format!("{summary}\n\n"));
+    }
+    out.push_str(&format!(
+        "Compared to {}, {} metrics without significant changes.\n",
+        short(&report.base),
+        report.unchanged
+    ));
+
+    for section in SECTIONS {
+        let changes = report
+            .changes
+            .iter()
+            .filter(|change| change.section == section)
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n### {section}\n\n"));
+        out.push_str("| Metric | Before | After | Change | |\n");
+        out.push_str("|---|---:|---:|---:|---|\n");
+        for change in changes {
+            let status = match (change.difference.significant, change.regression) {
+                (false, _) => "",
+                (true, true) => "regression",
+                (true, false) => "improvement",
+            };
+            out.push_str(&format!(
+                "| `{}` | {} | {} | {:+.2}% | {status} |\n",
+                change.metric.replace('|', "\\|"),
+                change.unit.format(change.difference.before),
+                change.unit.format(change.difference.after),
+                change.difference.magnitude * 100.0,
+            ));
+        }
+    }
+    out
+}
@@ -1,17 +1,34 @@
-use std::{collections::HashSet, fs::File, io::BufReader, path::Path};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs::File,
+    path::Path,
+};
 
 use chrono::{Days, Months};
-use git2::{Repository, Sort};
+use git2::{Oid, Repository, Sort};
 use regex::Regex;
 use serde::Serialize;
 use tera::Tera;
 use twitcher::{
-    file_safe_metric_name,
-    stats::{Stats, find_stats_files},
+    benchmark_ids::{BenchmarkId, file_name},
+    change_points::{self, NoiseModel, SIGNIFICANCE, Sample},
+    metric_key::MetricKey,
+    stats::{Query, Stats},
+    store,
 };
 
 const DATE_LIMIT: chrono::Duration = chrono::Duration::weeks(26);
 
+/// How long ago a change can have happened and still bring a test to the top of the list.
+const RECENT_CHANGES: chrono::Duration = chrono::Duration::days(30);
+
+/// Where the site is deployed, for the absolute links of the feed.
+const SITE_URL: &str = "https://bevyengine.github.io/twitcher/";
+
+/// Number of changes in the feed, the most recent ones.
+const FEED_ENTRIES: usize = 100;
+
 #[derive(Serialize)]
 struct Commit {
     id: String,
@@ -30,14 +47,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map(|id| format!(".{id}"))
         .unwrap_or("".to_string());
 
-    let stats: Vec<Stats> = find_stats_files(Path::new("results"))
-        .iter()
-        .map(|path| {
-            let file = File::open(path).unwrap();
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap()
-        })
-        .collect();
+    let stats: Vec<Stats> = store::load(
+        Path::new("results"),
+        &Query::default().since(chrono::Utc::now() - DATE_LIMIT),
+    );
 
     let repo = match Repository::open("bevy") {
         Ok(repo) => repo,
@@ -80,11 +93,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         })
         .collect::<Vec<_>>();
 
-    let crate_names = setup_compile_stats(&stats, &cache_id);
-    let stress_tests = setup_runtime("stress-test-fps", &stats, &cache_id);
-    let large_scenes = setup_runtime("large-scene-fps", &stats, &cache_id);
-    let mut benchmarks = setup_benchmarks(&stats, &cache_id);
+    // Crates changed since the previous measured commit, for each measured commit
+    let done = commits.iter().filter(|c| c.done).collect::<Vec<_>>();
+    let touched = done
+        .windows(2)
+        .map(|pair| {
+            (
+                pair[0].id.clone(),
+                touched_crates(&repo, &pair[1].id, &pair[0].id).join(", "),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let crate_names = setup_compile_stats(&stats, &touched, &cache_id);
+    let stress_tests = setup_runtime("stress-test-fps", &stats, &touched, &cache_id);
+    let large_scenes = setup_runtime("large-scene-fps", &stats, &touched, &cache_id);
+    let mut benchmarks = setup_benchmarks(&stats, &touched, &cache_id);
     setup_compare(&stats, &commits, &cache_id);
+    let feed = setup_feed(&stats, &commits, &repo);
 
     let mut runtime = stress_tests;
     runtime.extend(large_scenes);
@@ -96,21 +122,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .collect::<Vec<_>>();
     runtime.sort_by(|a, b| a.1.total_cmp(&b.1));
     runtime.reverse();
-    let stress_tests_z = runtime
+    let stress_tests_changes = runtime
         .iter()
         .map(|(name, _)| name.clone())
         .collect::<Vec<_>>();
 
-    let benchmarks_alpha = benchmarks
-        .iter()
-        .map(|(name, _)| name.clone())
-        .collect::<Vec<_>>();
-    benchmarks.sort_by(|a, b| a.1.total_cmp(&b.1));
-    benchmarks.reverse();
-    let benchmarks_z = benchmarks
-        .iter()
-        .map(|(name, _)| name.clone())
-        .collect::<Vec<_>>();
+    let benchmarks_alpha = serde_json::to_value(&benchmarks).unwrap();
+    benchmarks.sort_by(|a, b| b.score.total_cmp(&a.score));
+    for group in benchmarks.iter_mut() {
+        group.benchmarks.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+    let benchmarks_changes = serde_json::to_value(&benchmarks).unwrap();
 
     let tera = Tera::new("templates/*").unwrap();
     let mut context = tera::Context::new();
@@ -118,7 +140,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     context.insert("crate_names", &crate_names);
     context.insert("commits", &commits);
     context.insert("stress_tests", &stress_tests_alpha);
-    context.insert("benchmarks", &benchmarks_alpha);
+    context.insert("benchmark_groups", &benchmarks_alpha);
     context.insert(
         "start",
         &((chrono::Utc::now() - DATE_LIMIT).timestamp() * 1000),
@@ -162,7 +184,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     std::fs::write("./compile-stats.html", &rendered).unwrap();
 
     context.insert("stress_tests", &stress_tests_alpha);
-    context.insert("benchmarks", &benchmarks_alpha);
+    context.insert("benchmark_groups", &benchmarks_alpha);
 
     let rendered = tera.render("stress-tests.html", &context).unwrap();
     std::fs::write("./stress-tests_alpha.html", &rendered).unwrap();
@@ -170,8 +192,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let rendered = tera.render("benchmarks.html", &context).unwrap();
     std::fs::write("./benchmarks_alpha.html", &rendered).unwrap();
 
-    context.insert("stress_tests", &stress_tests_z);
-    context.insert("benchmarks", &benchmarks_z);
+    context.insert("stress_tests", &stress_tests_changes);
+    context.insert("benchmark_groups", &benchmarks_changes);
 
     let rendered = tera.render("stress-tests.html", &context).unwrap();
     std::fs::write("./stress-tests_z.html", &rendered).unwrap();
@@ -182,21 +204,39 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let rendered = tera.render("compare.html", &context).unwrap();
     std::fs::write("./compare.html", &rendered).unwrap();
 
+    context.insert("site_url", SITE_URL);
+    context.insert("updated", &chrono::Utc::now().to_rfc3339());
+    context.insert("entries", &feed);
+    let rendered = tera.render("feed.xml", &context).unwrap();
+    std::fs::write("./feed.xml", &rendered).unwrap();
+
     Ok(())
 }
 
-fn setup_compile_stats<'a>(stats: &'a [Stats], cache_id: &str) -> Vec<&'a str> {
+fn setup_compile_stats(
+    stats: &[Stats],
+    touched: &HashMap<String, String>,
+    cache_id: &str,
+) -> Vec<String> {
     #[derive(Serialize)]
     struct DataPoint {
         timestamp: u128,
         commit: String,
-        value: u64,
+        value: f64,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        crates: String,
     }
 
     let compilation_keys: HashSet<_> = stats
         .iter()
         .flat_map(|stat| stat.metrics.keys())
-        .filter(|k| (k.contains("compile-time") && k.ends_with("mean")) || k.ends_with("size"))
+        .filter(|k| {
+            k.parse::<MetricKey>().is_ok_and(|key| {
+                (key.family.ends_with("compile-time") && key.statistic.ends_with("mean"))
+                    || key.statistic == "size"
+                    || (key.family == "dependencies" && key.statistic == "count")
+            })
+        })
         .collect();
     compilation_keys.into_iter().for_each(|metric| {
         let values = stats
@@ -211,7 +251,8 @@ fn setup_compile_stats<'a>(stats: &'a [Stats], cache_id: &str) -> Vec<&'a str> {
                 stat.metrics.get(metric).map(|value| DataPoint {
                     timestamp: stat.commit_timestamp,
                     commit: stat.commit.clone(),
-                    value: *value,
+                    value: value.value,
+                    crates: touched.get(&stat.commit).cloned().unwrap_or_default(),
                 })
             })
             .collect::<Vec<_>>();
@@ -225,8 +266,9 @@ fn setup_compile_stats<'a>(stats: &'a [Stats], cache_id: &str) -> Vec<&'a str> {
     let mut crate_names = stats
         .iter()
         .flat_map(|stat| stat.metrics.keys())
-        .filter(|m| m.starts_with("crate-compile-time") && m.ends_with(".mean"))
-        .map(|m| m.split('.').nth(1).unwrap())
+        .filter_map(|m| m.parse::<MetricKey>().ok())
+        .filter(|key| key.family == "crate-compile-time" && key.statistic == "mean")
+        .filter_map(|key| key.subject)
         .collect::<HashSet<_>>()
         .into_iter()
         .collect::<Vec<_>>();
@@ -234,30 +276,34 @@ fn setup_compile_stats<'a>(stats: &'a [Stats], cache_id: &str) -> Vec<&'a str> {
     crate_names
 }
 
-fn setup_runtime(kind: &str, stats: &[Stats], cache_id: &str) -> Vec<(String, f64)> {
+fn setup_runtime(
+    kind: &str,
+    stats: &[Stats],
+    touched: &HashMap<String, String>,
+    cache_id: &str,
+) -> Vec<(String, f64)> {
     #[derive(Serialize)]
     struct DataPoint {
         timestamp: u128,
         commit: String,
-        frame_time: u64,
-        cpu: Option<u64>,
-        gpu: Option<u64>,
+        frame_time: f64,
+        cpu: Option<f64>,
+        gpu: Option<f64>,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        crates: String,
     }
 
     let mut stress_tests = stats
         .iter()
         .flat_map(|stat| stat.metrics.keys())
-        .filter(|m| m.starts_with(kind) && m.ends_with(".mean"))
-        .map(|m| {
-            let mut split = m.split('.');
-            (split.nth(1).unwrap(), split.next().unwrap())
-        })
+        .filter_map(|m| m.parse::<MetricKey>().ok())
+        .filter(|key| key.family == kind && key.dimension.is_none() && key.statistic == "mean")
         .collect::<HashSet<_>>()
         .into_iter()
         .collect::<Vec<_>>();
     stress_tests.sort();
 
-    let with_z_scores = stress_tests
+    let with_scores = stress_tests
         .into_iter()
         .flat_map(|stress_test| {
             let values = stats
@@ -272,30 +318,19 @@ fn setup_runtime(kind: &str, stats: &[Stats], cache_id: &str) -> Vec<(String, f6
                             > chrono::DateTime::parse_from_rfc3339("2026-03-30T12:00:00Z").unwrap() // Data before this date is not with the same format
                 })
                 .flat_map(|stat| {
-                    stat.metrics
-                        .get(&format!(
-                            "{kind}.{}.{}.frame_time.mean",
-                            stress_test.0, stress_test.1
-                        ))
-                        .map(|frame_time| DataPoint {
-                            timestamp: stat.commit_timestamp,
-                            commit: stat.commit.clone(),
-                            frame_time: *frame_time,
-                            cpu: stat
-                                .metrics
-                                .get(&format!(
-                                    "{kind}.{}.{}.cpu_load.mean",
-                                    stress_test.0, stress_test.1
-                                ))
-                                .cloned(),
-                            gpu: stat
-                                .metrics
-                                .get(&format!(
-                                    "{kind}.{}.{}.gpu_load.mean",
-                                    stress_test.0, stress_test.1
-                                ))
-                                .cloned(),
-                        })
+                    let get = |dimension: &str| {
+                        stat.metrics
+                            .get(&stress_test.clone().with_dimension(dimension).to_string())
+                            .map(|value| value.value)
+                    };
+                    get("frame_time").map(|frame_time| DataPoint {
+                        timestamp: stat.commit_timestamp,
+                        commit: stat.commit.clone(),
+                        frame_time,
+                        cpu: get("cpu_usage"),
+                        gpu: get("gpu_usage"),
+                        crates: touched.get(&stat.commit).cloned().unwrap_or_default(),
+                    })
                 })
                 .collect::<Vec<_>>();
 
@@ -303,7 +338,9 @@ fn setup_runtime(kind: &str, stats: &[Stats], cache_id: &str) -> Vec<(String, f6
                 return None;
             }
 
-            let file_name = format!("{}{}", stress_test.0, stress_test.1.replace("params", ""),);
+            let name = stress_test.subject.clone().unwrap_or_default();
+            let params = stress_test.variant.clone().unwrap_or_default();
+            let file_name = format!("{name}{}", params.replace("params", ""));
 
             serde_json::to_writer(
                 std::fs::File::create(format!("data/{file_name}{cache_id}.json")).unwrap(),
@@ -311,126 +348,168 @@ fn setup_runtime(kind: &str, stats: &[Stats], cache_id: &str) -> Vec<(String, f6
             )
             .unwrap();
 
-            let last_week_z_score = if values.len() > 5 {
-                let raw_values = values
-                    .iter()
-                    .map(|v| v.frame_time as f64)
-                    .collect::<Vec<_>>();
-                let mean = statistical::mean(raw_values.as_slice());
-                let standard_deviation =
-                    statistical::standard_deviation(raw_values.as_slice(), None);
-
-                values
-                    .iter()
-                    .filter(|data| {
-                        (chrono::Utc::now()
-                            - chrono::DateTime::from_timestamp_millis(data.timestamp as i64)
-                                .unwrap())
-                            <= chrono::Duration::days(7)
-                    })
-                    .map(|data| data.frame_time)
-                    .map(|v| ((v as f64 - mean) / standard_deviation).abs())
-                    .max_by(|a, b| a.total_cmp(b))
-                    .unwrap_or_default()
-            } else {
-                0.0
-            };
-            Some((stress_test.0, stress_test.1, last_week_z_score))
+            let series = values
+                .iter()
+                .map(|data| Sample {
+                    commit: data.commit.clone(),
+                    timestamp: data.timestamp,
+                    value: data.frame_time,
+                })
+                .collect::<Vec<_>>();
+            let score = change_score(&stress_test.with_dimension("frame_time"), &series);
+            Some((name, params, score))
         })
         .collect::<Vec<_>>();
 
-    with_z_scores
+    with_scores
         .into_iter()
-        .map(|(name, params, z_score)| {
+        .map(|(name, params, score)| {
             let file_name = format!("{name}{}", params.replace("params", ""));
-            (file_name.clone(), z_score)
+            (file_name.clone(), score)
         })
         .collect()
 }
 
-fn setup_benchmarks(stats: &[Stats], cache_id: &str) -> Vec<(String, f64)> {
+#[derive(Serialize)]
+struct BenchmarkGroup {
+    name: String,
+    file: String,
+    score: f64,
+    benchmarks: Vec<Benchmark>,
+}
+
+#[derive(Serialize)]
+struct Benchmark {
+    id: String,
+    file: String,
+    function: String,
+    parameter: Option<String>,
+    score: f64,
+}
+
+fn setup_benchmarks(
+    stats: &[Stats],
+    touched: &HashMap<String, String>,
+    cache_id: &str,
+) -> Vec<BenchmarkGroup> {
     #[derive(Serialize)]
     struct DataPoint {
         timestamp: u128,
         commit: String,
-        duration: u64,
+        duration: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        lower: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        upper: Option<f64>,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        crates: String,
     }
 
+    let _ = std::fs::create_dir("data/benchmarks");
+
     let mut benchmarks = stats
         .iter()
         .flat_map(|stat| stat.metrics.keys())
-        .filter(|m| m.starts_with("benchmarks.") && m.ends_with(".mean"))
-        .map(|m| {
-            let mut split = m.split('.');
-            split.nth(1).unwrap()
-        })
-        .map(|benchmark| (benchmark.to_string(), file_safe_metric_name(benchmark)))
+        .filter_map(|m| m.parse::<MetricKey>().ok())
+        .filter(|key| key.family == "benchmarks" && key.statistic == "mean")
         .collect::<HashSet<_>>()
         .into_iter()
         .collect::<Vec<_>>();
     benchmarks.sort();
 
-    let with_z_scores = benchmarks
-        .into_iter()
-        .flat_map(|(benchmark, safe_name)| {
-            let values = stats
-                .iter()
-                .filter(|stat| {
-                    (chrono::Utc::now()
-                        - chrono::DateTime::from_timestamp_millis(stat.commit_timestamp as i64)
-                            .unwrap())
-                        <= DATE_LIMIT
-                })
-                .flat_map(|stat| {
-                    stat.metrics
-                        .get(&format!("benchmarks.{benchmark}.mean"))
-                        .map(|value| DataPoint {
-                            timestamp: stat.commit_timestamp,
-                            commit: stat.commit.clone(),
-                            duration: *value,
-                        })
+    let mut group_values: BTreeMap<String, HashMap<String, Vec<DataPoint>>> = BTreeMap::new();
+    let mut group_benchmarks: BTreeMap<String, Vec<Benchmark>> = BTreeMap::new();
+
+    for key in benchmarks {
+        let metric = key.to_string();
+        let benchmark = key.subject.clone().unwrap_or_default();
+        let values = stats
+            .iter()
+            .filter(|stat| {
+                (chrono::Utc::now()
+                    - chrono::DateTime::from_timestamp_millis(stat.commit_timestamp as i64)
+                        .unwrap())
+                    <= DATE_LIMIT
+            })
+            .flat_map(|stat| {
+                stat.metrics.get(&metric).map(|value| DataPoint {
+                    timestamp: stat.commit_timestamp,
+                    commit: stat.commit.clone(),
+                    duration: value.value,
+                    lower: value.lower,
+                    upper: value.upper,
+                    crates: touched.get(&stat.commit).cloned().unwrap_or_default(),
                 })
-                .collect::<Vec<_>>();
+            })
+            .collect::<Vec<_>>();
 
-            if values.is_empty() {
-                return None;
-            }
+        if values.is_empty() {
+            continue;
+        }
+
+        let series = values
+            .iter()
+            .map(|data| Sample {
+                commit: data.commit.clone(),
+                timestamp: data.timestamp,
+                value: data.duration,
+            })
+            .collect::<Vec<_>>();
+        let score = change_score(&key, &series);
+
+        let id = BenchmarkId::parse(&benchmark);
+        group_values
+            .entry(id.group.clone())
+            .or_default()
+            .insert(benchmark.clone(), values);
+        group_benchmarks
+            .entry(id.group)
+            .or_default()
+            .push(Benchmark {
+                file: file_name(&benchmark),
+                id: benchmark,
+                function: id.function,
+                parameter: id.parameter,
+                score,
+            });
+    }
 
+    group_benchmarks
+        .into_iter()
+        .map(|(name, benchmarks)| {
+            let file = file_name(&name);
+            let values = &group_values[&name];
             serde_json::to_writer(
-                std::fs::File::create(format!("data/{safe_name}{cache_id}.json")).unwrap(),
-                &values,
+                std::fs::File::create(format!("data/benchmarks/{file}{cache_id}.json")).unwrap(),
+                values,
             )
             .unwrap();
 
-            let last_week_z_score = if values.len() > 5 {
-                let raw_values = values.iter().map(|v| v.duration as f64).collect::<Vec<_>>();
-                let mean = statistical::mean(raw_values.as_slice());
-                let standard_deviation =
-                    statistical::standard_deviation(raw_values.as_slice(), None);
-
-                values
+            BenchmarkGroup {
+                score: benchmarks
                     .iter()
-                    .filter(|data| {
-                        (chrono::Utc::now()
-                            - chrono::DateTime::from_timestamp_millis(data.timestamp as i64)
-                                .unwrap())
-                            <= chrono::Duration::days(7)
-                    })
-                    .map(|data| data.duration)
-                    .map(|v| ((v as f64 - mean) / standard_deviation).abs())
+                    .map(|b| b.score)
                     .max_by(|a, b| a.total_cmp(b))
-                    .unwrap_or_default()
-            } else {
-                0.0
-            };
-            Some((benchmark, safe_name, last_week_z_score))
+                    .unwrap_or_default(),
+                name,
+                file,
+                benchmarks,
+            }
         })
-        .collect::<Vec<_>>();
+        .collect::<Vec<_>>()
+}
 
-    with_z_scores
-        .into_iter()
-        .map(|(_, safe_name, z_score)| (safe_name, z_score))
-        .collect()
+/// Score used to order tests, the largest recent change of their series weighted by how
+/// confident the detection is.
+fn change_score(key: &MetricKey, series: &[Sample]) -> f64 {
+    let values = series.iter().map(|sample| sample.value).collect::<Vec<_>>();
+    let noise = NoiseModel::for_metric(key, &values);
+    let recent = (chrono::Utc::now() - RECENT_CHANGES).timestamp_millis() as u128;
+    change_points::detect(series, &noise)
+        .iter()
+        .filter(|change| change.timestamp >= recent)
+        .map(|change| change.magnitude.abs() * change.confidence)
+        .fold(0.0, f64::max)
 }
 
 fn setup_compare(stats: &[Stats], commits: &[Commit], cache_id: &str) {
@@ -446,3 +525,132 @@ fn setup_compare(stats: &[Stats], commits: &[Commit], cache_id: &str) {
         }
     }
 }
+
+#[derive(Serialize)]
+struct FeedEntry {
+    title: String,
+    link: String,
+    metric: String,
+    updated: String,
+    /// Escaped HTML
+    content: String,
+    #[serde(skip)]
+    timestamp: u128,
+}
+
+/// One entry per significant change detected in a tracked metric, most recent first.
+fn setup_feed(stats: &[Stats], commits: &[Commit], repo: &Repository) -> Vec<FeedEntry> {
+    let metrics = stats
+        .iter()
+        .flat_map(|stat| stat.metrics.keys())
+        .collect::<HashSet<_>>();
+
+    let mut entries = metrics
+        .into_iter()
+        .filter_map(|metric| Some((metric, metric.parse::<MetricKey>().ok()?)))
+        .filter(|(_, key)| key.is_tracked())
+        .flat_map(|(metric, key)| {
+            let series = Sample::series(stats, metric);
+            let values = series.iter().map(|sample| sample.value).collect::<Vec<_>>();
+            let noise = NoiseModel::for_metric(&key, &values);
+            change_points::detect(&series, &noise)
+                .into_iter()
+                .filter(|change| change.confidence >= SIGNIFICANCE)
+                .map(|change| {
+                    let base = &series[change.index - 1].commit;
+                    let commit = commits.iter().find(|commit| commit.id == change.commit);
+                    let regression = (change.magnitude > 0.0) != key.higher_is_better();
+                    let unit = key.unit();
+
+                    let mut content = format!(
+                        "<p><code>{}</code> went from {} to {} ({:+.2}%, {:.1}% confidence).</p><p>",
+                        tera::escape_html(metric),
+                        unit.format(change.before),
+                        unit.format(change.after),
+                        change.magnitude * 100.0,
+                        change.confidence * 100.0,
+                    );
+                    if let Some(commit) = commit {
+                        content.push_str(&tera::escape_html(&commit.summary));
+                        if commit.pr != 0 {
+                            content.push_str(&format!(
+                                " (<a href=\"https://github.com/bevyengine/bevy/pull/{0}\">#{0}</a>)",
+                                commit.pr
+                            ));
+                        }
+                        content.push_str(", ");
+                    }
+                    content.push_str(&format!(
+                        "commit <a href=\"https://github.com/bevyengine/bevy/commit/{}\">{}</a></p>",
+                        change.commit,
+                        &change.commit[..10]
+                    ));
+                    let crates = touched_crates(repo, base, &change.commit);
+                    if !crates.is_empty() {
+                        let subject = key.subject.as_deref();
+                        let crates = crates
+                            .iter()
+                            .map(|name| {
+                                // Highlight the crate the metric is about, like for crate compile times
+                                if Some(name.as_str()) == subject {
+                                    format!("<b>{name}</b>")
+                                } else {
+                                    name.clone()
+                                }
+                            })
+                            .collect::<Vec<_>>();
+                        content.push_str(&format!(
+                            "<p>Crates touched since {}: {}</p>",
+                            &base[..10],
+                            crates.join(", ")
+                        ));
+                    }
+
+                    FeedEntry {
+                        title: format!(
+                            "{} of {:+.2}% in {metric}",
+                            if regression { "Regression" } else { "Improvement" },
+                            change.magnitude * 100.0
+                        ),
+                        link: format!("{SITE_URL}compare.html?base={base}&other={}", change.commit),
+                        metric: metric.clone(),
+                        updated: chrono::DateTime::from_timestamp_millis(change.timestamp as i64)
+                            .unwrap()
+                            .to_rfc3339(),
+                        content,
+                        timestamp: change.timestamp,
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    entries.sort_by_key(|entry| Reverse(entry.timestamp));
+    entries.truncate(FEED_ENTRIES);
+    entries
+}
+
+/// Bevy crates with a file changed between two commits, from the paths under `crates/bevy_*`.
+fn touched_crates(repo: &Repository, from: &str, to: &str) -> Vec<String> {
+    let tree = |id: &str| repo.find_commit(Oid::from_str(id).ok()?).ok()?.tree().ok();
+    let (Some(from), Some(to)) = (tree(from), tree(to)) else {
+        return vec![];
+    };
+    let Ok(diff) = repo.diff_tree_to_tree(Some(&from), Some(&to), None) else {
+        return vec![];
+    };
+    diff.deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .filter_map(|path| {
+            let mut components = path.iter();
+            if components.next()? != "crates" {
+                return None;
+            }
+            let name = components.next()?.to_str()?;
+            name.starts_with("bevy_").then(|| name.to_string())
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
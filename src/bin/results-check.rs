@@ -0,0 +1,342 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::Parser;
+use git2::{Oid, Repository};
+use twitcher::stats::{Stats, Unit, read_stats};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Check the results folder for inconsistencies", long_about = None)]
+struct Cli {
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+
+    /// Fix the problems that can be fixed without losing data
+    #[arg(long)]
+    fix: bool,
+
+    /// Checkout of Bevy, misplaced stats are only moved to commits that exist in it
+    #[arg(long, default_value = "bevy")]
+    bevy: String,
+
+    /// Hosts seen for the first time in results run during the last days are reported
+    #[arg(long, default_value_t = 30)]
+    new_host_days: u64,
+
+    /// Host that is expected to produce results, never reported as new
+    #[arg(long)]
+    known_host: Vec<String>,
+}
+
+struct Problem {
+    path: PathBuf,
+    description: String,
+    fix: Option<Fix>,
+}
+
+enum Fix {
+    /// Move the folder to where it should be
+    Move(PathBuf),
+    /// Remove the file or folder, it holds nothing that isn't already somewhere else
+    Remove,
+}
+
+impl Problem {
+    fn new(path: &Path, description: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            description: description.into(),
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let root = Path::new(&cli.results);
+    let repo = Repository::open(&cli.bevy)
+        .inspect_err(|error| eprintln!("misplaced stats won't be moved: {error}"))
+        .ok();
+
+    let mut problems = vec![];
+    let mut commit_dirs = vec![];
+    walk(root, 0, &mut problems, &mut commit_dirs);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let mut loaded = vec![];
+    // Folders that are, or will be after fixing, where a commit is stored
+    let mut claimed: HashSet<PathBuf> = commit_dirs.iter().cloned().collect();
+    for dir in &commit_dirs {
+        let file = dir.join("stats.json");
+        let stats = match read_stats(&file) {
+            Ok(stats) => stats,
+            Err(error) => {
+                problems.push(Problem::new(
+                    &file,
+                    format!("invalid stats: {}", error.error),
+                ));
+                continue;
+            }
+        };
+        check_location(
+            root,
+            dir,
+            &stats,
+            repo.as_ref(),
+            &mut claimed,
+            &mut problems,
+        );
+        check_timestamps(&file, &stats, now, &mut problems);
+        check_values(&file, &stats, &mut problems);
+        loaded.push((file, stats));
+    }
+    check_hosts(&cli, &loaded, now, &mut problems);
+
+    problems.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut remaining = 0;
+    for problem in &problems {
+        match (&problem.fix, cli.fix) {
+            (Some(fix), true) => {
+                let done = match fix {
+                    Fix::Move(to) => fs::create_dir_all(to.parent().unwrap())
+                        .and_then(|_| fs::rename(&problem.path, to))
+                        .map(|_| format!("moved to {}", to.display())),
+                    Fix::Remove if problem.path.is_dir() => {
+                        fs::remove_dir_all(&problem.path).map(|_| "removed".to_string())
+                    }
+                    Fix::Remove => fs::remove_file(&problem.path).map(|_| "removed".to_string()),
+                };
+                match done {
+                    Ok(done) => {
+                        println!(
+                            "{}: {} ({done})",
+                            problem.path.display(),
+                            problem.description
+                        )
+                    }
+                    Err(error) => {
+                        remaining += 1;
+                        println!(
+                            "{}: {} (fix failed: {error})",
+                            problem.path.display(),
+                            problem.description
+                        );
+                    }
+                }
+            }
+            (fix, _) => {
+                remaining += 1;
+                let fixable = if fix.is_some() { " (fixable)" } else { "" };
+                println!(
+                    "{}: {}{fixable}",
+                    problem.path.display(),
+                    problem.description
+                );
+            }
+        }
+    }
+
+    println!(
+        "{} stats checked, {} problems, {remaining} remaining",
+        commit_dirs.len(),
+        problems.len()
+    );
+    if remaining > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Goes through the `<c0>/<c1>/<commit>` layout, collecting the commit folders and reporting
+/// anything else.
+fn walk(dir: &Path, depth: usize, problems: &mut Vec<Problem>, commit_dirs: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        problems.push(Problem::new(dir, "unreadable folder"));
+        return;
+    };
+    let mut entries = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if depth == 0 && (name.starts_with('.') || path.is_file()) {
+            // The branch itself can have a readme or a .git folder
+            continue;
+        }
+        if path.is_file() {
+            // Empty files are leftover markers, anything else may be a result worth keeping
+            if fs::metadata(&path).is_ok_and(|m| m.len() == 0) {
+                problems.push(Problem::new(&path, "unexpected marker").with_fix(Fix::Remove));
+            } else {
+                problems.push(Problem::new(&path, "unexpected file"));
+            }
+            continue;
+        }
+        if depth < 2 {
+            if name.len() != 1 || !is_hex(&name) {
+                problems.push(Problem::new(&path, "unexpected folder"));
+                continue;
+            }
+            walk(&path, depth + 1, problems, commit_dirs);
+        } else if path.join("stats.json").is_file() {
+            commit_dirs.push(path);
+        } else if only_markers(&path) {
+            problems.push(Problem::new(&path, "folder without stats").with_fix(Fix::Remove));
+        } else {
+            problems.push(Problem::new(&path, "artifacts without stats"));
+        }
+    }
+}
+
+fn check_location(
+    root: &Path,
+    dir: &Path,
+    stats: &Stats,
+    repo: Option<&Repository>,
+    claimed: &mut HashSet<PathBuf>,
+    problems: &mut Vec<Problem>,
+) {
+    if stats.commit.len() != 40 || !is_hex(&stats.commit) {
+        problems.push(Problem::new(
+            dir,
+            format!("invalid commit hash {:?}", stats.commit),
+        ));
+        return;
+    }
+    let expected = root
+        .join(&stats.commit[0..1])
+        .join(&stats.commit[1..2])
+        .join(&stats.commit);
+    if dir == expected {
+        return;
+    }
+    let problem = Problem::new(
+        dir,
+        format!(
+            "holds the stats of {}, expected in {}",
+            stats.commit,
+            expected.display()
+        ),
+    );
+    // A hash that doesn't resolve is more likely a corrupted field than a misplaced folder
+    let known = repo.is_some_and(|repo| {
+        Oid::from_str(&stats.commit).is_ok_and(|oid| repo.find_commit(oid).is_ok())
+    });
+    if !claimed.contains(&expected) {
+        if known {
+            claimed.insert(expected.clone());
+            problems.push(problem.with_fix(Fix::Move(expected)));
+        } else {
+            problems.push(problem);
+        }
+    } else if expected.is_dir() && is_copy_of(dir, &expected) {
+        problems.push(Problem {
+            description: format!("duplicate of {}", expected.display()),
+            ..problem.with_fix(Fix::Remove)
+        });
+    } else {
+        problems.push(Problem {
+            description: format!("duplicate of {} with different content", expected.display()),
+            ..problem
+        });
+    }
+}
+
+fn check_timestamps(file: &Path, stats: &Stats, now: u128, problems: &mut Vec<Problem>) {
+    if stats.commit_timestamp == 0 {
+        problems.push(Problem::new(file, "missing commit timestamp"));
+    } else if stats.commit_timestamp > now {
+        problems.push(Problem::new(file, "commit timestamp in the future"));
+    }
+    if stats.timestamp > now {
+        problems.push(Problem::new(file, "run timestamp in the future"));
+    } else if stats.commit_timestamp > stats.timestamp {
+        problems.push(Problem::new(file, "run before the commit it measured"));
+    }
+}
+
+fn check_values(file: &Path, stats: &Stats, problems: &mut Vec<Problem>) {
+    let metrics = stats.metrics.iter().collect::<BTreeMap<_, _>>();
+    for (name, value) in metrics {
+        let impossible = if !value.value.is_finite() {
+            Some("is not a number")
+        } else if value.value < 0.0 {
+            Some("is negative")
+        } else if value.value == 0.0 && matches!(value.unit, Unit::Fps | Unit::Bytes) {
+            Some("is zero")
+        } else if value.lower.zip(value.upper).is_some_and(|(l, u)| l > u) {
+            Some("has its lower bound above its upper bound")
+        } else {
+            None
+        };
+        if let Some(impossible) = impossible {
+            problems.push(Problem::new(file, format!("{name} {impossible}: {value}")));
+        }
+    }
+}
+
+fn check_hosts(cli: &Cli, loaded: &[(PathBuf, Stats)], now: u128, problems: &mut Vec<Problem>) {
+    let recent = now.saturating_sub(Duration::from_secs(cli.new_host_days * 86400).as_millis());
+    let mut known = cli.known_host.iter().cloned().collect::<HashSet<_>>();
+    known.extend(
+        loaded
+            .iter()
+            .filter(|(_, stats)| stats.timestamp < recent)
+            .map(|(_, stats)| stats.host.hostname.clone()),
+    );
+    let mut reported = HashSet::new();
+    let mut by_date = loaded.iter().collect::<Vec<_>>();
+    by_date.sort_by_key(|(_, stats)| stats.timestamp);
+    for (file, stats) in by_date {
+        let hostname = &stats.host.hostname;
+        if !known.contains(hostname) && reported.insert(hostname) {
+            problems.push(Problem::new(
+                file,
+                format!("first results from new host {hostname:?}"),
+            ));
+        }
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Folders that only contain empty files, like the markers left by an interrupted run, directly
+/// or in artifact folders like `wasm32-unknown-unknown-size.stats/done`.
+fn only_markers(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().all(|entry| {
+            entry.metadata().is_ok_and(|m| {
+                (m.is_file() && m.len() == 0) || (m.is_dir() && only_markers(&entry.path()))
+            })
+        })
+    })
+}
+
+/// Whether every file under `dir` is also under `other`, with the same content.
+fn is_copy_of(dir: &Path, other: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries.flatten().all(|entry| {
+        let path = entry.path();
+        let counterpart = other.join(entry.file_name());
+        if path.is_dir() {
+            is_copy_of(&path, &counterpart)
+        } else {
+            matches!((fs::read(&path), fs::read(&counterpart)), (Ok(a), Ok(b)) if a == b)
+        }
+    })
+}
@@ -1,44 +1,188 @@
-use std::{collections::HashSet, fs::File, io::BufReader, path::Path};
+use std::{collections::BTreeSet, path::Path};
 
-use twitcher::stats::{Stats, find_stats_files};
+use clap::{Parser, ValueEnum};
+use twitcher::{
+    stats::{Query, Stats},
+    store,
+};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("drop table if exists metrics;");
-    println!("create table metrics (timestamp timestamp, commit text, name text, value bigint);");
+#[derive(Parser, Debug)]
+#[command(version, about = "Export the results as SQL statements", long_about = None)]
+struct Cli {
+    /// Database the statements are written for
+    #[arg(short, long, value_enum, default_value_t = Dialect::Postgres)]
+    dialect: Dialect,
 
-    let stats: Vec<Stats> = find_stats_files(Path::new("results"))
-        .iter()
-        .map(|path| {
-            let file = File::open(path).unwrap();
-            let reader = BufReader::new(file);
-            serde_json::from_reader(reader).unwrap()
+    /// Only export the results of runs after this watermark, without recreating the tables.
+    /// Either a date like `2025-06-01T00:00:00Z` or a timestamp in milliseconds, as printed at
+    /// the end of the previous export
+    #[arg(short, long)]
+    after: Option<String>,
+
+    /// Folder with the results
+    #[arg(short, long, default_value = "results")]
+    results: String,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    Sqlite,
+    Postgres,
+}
+
+impl Dialect {
+    fn id_column(&self) -> &'static str {
+        match self {
+            Dialect::Sqlite => "id integer primary key",
+            Dialect::Postgres => "id serial primary key",
+        }
+    }
+
+    fn timestamp_type(&self) -> &'static str {
+        match self {
+            // SQLite has no timestamp type, ISO 8601 text sorts and works with its date functions
+            Dialect::Sqlite => "text",
+            Dialect::Postgres => "timestamptz",
+        }
+    }
+}
+
+/// Quotes a string literal, the same way for both dialects.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn timestamp(millis: u128) -> String {
+    let timestamp = chrono::DateTime::from_timestamp_millis(millis as i64).unwrap_or_default();
+    quote(&timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+fn number(value: Option<f64>) -> String {
+    match value {
+        Some(value) if value.is_finite() => format!("{value:?}"),
+        _ => "null".to_string(),
+    }
+}
+
+fn parse_watermark(watermark: &str) -> Option<u128> {
+    watermark.parse().ok().or_else(|| {
+        chrono::DateTime::parse_from_rfc3339(watermark)
+            .ok()
+            .map(|date| date.timestamp_millis() as u128)
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let watermark = cli.after.as_ref().map(|after| {
+        parse_watermark(after).unwrap_or_else(|| {
+            eprintln!("invalid watermark {after}");
+            std::process::exit(1);
         })
-        .collect();
+    });
 
-    let keys: HashSet<_> = stats.iter().flat_map(|stat| stat.metrics.keys()).collect();
-    let values = keys
+    let stats: Vec<Stats> = store::load(Path::new(&cli.results), &Query::default())
         .into_iter()
-        .flat_map(|metric| {
-            stats
-                .iter()
-                .flat_map(|stat| {
-                    stat.metrics.get(metric).map(|value| {
-                        (
-                            chrono::DateTime::from_timestamp_millis(stat.commit_timestamp as i64)
-                                .unwrap(),
-                            stat.commit.clone(),
-                            *value,
-                        )
-                    })
-                })
-                .map(|(ts, commit, value)| {
-                    format!("('{}', '{}', '{}', {})", ts, commit, metric.clone(), value)
-                })
-        })
-        .collect::<Vec<_>>();
-    values
-        .chunks(1000)
-        .for_each(|chunk| println!("insert into metrics values {};", chunk.join(",")));
+        .filter(|stats| watermark.is_none_or(|watermark| stats.timestamp > watermark))
+        .collect();
+
+    let timestamp_type = cli.dialect.timestamp_type();
+    if watermark.is_none() {
+        println!("drop table if exists metrics;");
+        println!("drop table if exists commits;");
+        println!("drop table if exists hosts;");
+        println!("drop table if exists toolchains;");
+    }
+    println!(
+        "create table if not exists hosts ({}, hostname text not null, os_version text not null, unique (hostname, os_version));",
+        cli.dialect.id_column()
+    );
+    println!(
+        "create table if not exists toolchains ({}, stable text not null, nightly text not null, unique (stable, nightly));",
+        cli.dialect.id_column()
+    );
+    println!(
+        "create table if not exists commits (hash text primary key, commit_timestamp {timestamp_type} not null, run_timestamp {timestamp_type} not null, host_id integer not null references hosts (id), toolchain_id integer not null references toolchains (id));"
+    );
+    println!(
+        "create table if not exists metrics (commit_hash text not null references commits (hash), name text not null, value double precision not null, unit text not null, lower double precision, upper double precision, primary key (commit_hash, name));"
+    );
+    println!("begin;");
+
+    let hosts: BTreeSet<_> = stats
+        .iter()
+        .map(|stats| (&stats.host.hostname, &stats.host.os_version))
+        .collect();
+    for (hostname, os_version) in hosts {
+        println!(
+            "insert into hosts (hostname, os_version) values ({}, {}) on conflict do nothing;",
+            quote(hostname),
+            quote(os_version)
+        );
+    }
+    let toolchains: BTreeSet<_> = stats
+        .iter()
+        .map(|stats| (&stats.rust.stable, &stats.rust.nightly))
+        .collect();
+    for (stable, nightly) in toolchains {
+        println!(
+            "insert into toolchains (stable, nightly) values ({}, {}) on conflict do nothing;",
+            quote(stable),
+            quote(nightly)
+        );
+    }
 
-    Ok(())
+    for stat in &stats {
+        // A commit can be measured again with more metrics, the latest run wins
+        println!(
+            "insert into commits values ({}, {}, {}, (select id from hosts where hostname = {} and os_version = {}), (select id from toolchains where stable = {} and nightly = {})) on conflict (hash) do update set run_timestamp = excluded.run_timestamp, host_id = excluded.host_id, toolchain_id = excluded.toolchain_id;",
+            quote(&stat.commit),
+            timestamp(stat.commit_timestamp),
+            timestamp(stat.timestamp),
+            quote(&stat.host.hostname),
+            quote(&stat.host.os_version),
+            quote(&stat.rust.stable),
+            quote(&stat.rust.nightly),
+        );
+
+        let mut metrics = stat.metrics.iter().collect::<Vec<_>>();
+        metrics.sort_by_key(|(name, _)| *name);
+        let values = metrics
+            .into_iter()
+            .filter(|(name, value)| {
+                let finite = value.value.is_finite();
+                if !finite {
+                    eprintln!("skipping {name} of {}: {}", stat.commit, value.value);
+                }
+                finite
+            })
+            .map(|(name, value)| {
+                format!(
+                    "({}, {}, {}, {}, {}, {})",
+                    quote(&stat.commit),
+                    quote(name),
+                    number(Some(value.value)),
+                    quote(&value.unit.to_string()),
+                    number(value.lower),
+                    number(value.upper)
+                )
+            })
+            .collect::<Vec<_>>();
+        values.chunks(1000).for_each(|chunk| {
+            println!(
+                "insert into metrics values {} on conflict (commit_hash, name) do update set value = excluded.value, unit = excluded.unit, lower = excluded.lower, upper = excluded.upper;",
+                chunk.join(",")
+            )
+        });
+    }
+
+    println!("commit;");
+    if let Some(latest) = stats
+        .iter()
+        .map(|stats| stats.timestamp)
+        .max()
+        .or(watermark)
+    {
+        println!("-- watermark: {latest}");
+    }
 }
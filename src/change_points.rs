@@ -0,0 +1,327 @@
+use serde::Serialize;
+
+use crate::{
+    metric_key::MetricKey,
+    samples::erfc,
+    stats::{Stats, Unit},
+};
+
+/// A value of a metric, measured on a commit.
+#[derive(Serialize, Debug, Clone)]
+pub struct Sample {
+    pub commit: String,
+    pub timestamp: u128,
+    pub value: f64,
+}
+
+impl Sample {
+    /// The series of a metric over `stats`, in the order of `stats`.
+    pub fn series(stats: &[Stats], metric: &str) -> Vec<Sample> {
+        stats
+            .iter()
+            .filter_map(|stat| {
+                stat.metrics.get(metric).map(|value| Sample {
+                    commit: stat.commit.clone(),
+                    timestamp: stat.commit_timestamp,
+                    value: value.value,
+                })
+            })
+            .collect()
+    }
+}
+
+/// How much a metric varies between runs when nothing changed.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct NoiseModel {
+    /// Standard deviation of the noise
+    pub sigma: f64,
+    /// Relative changes smaller than this are never reported
+    pub relative_floor: f64,
+}
+
+impl NoiseModel {
+    /// Estimates the noise from the differences between consecutive values. Unlike the
+    /// standard deviation of the whole series, it is barely affected by a step change, which
+    /// only shows up in a single difference, or by a slow drift.
+    pub fn estimate(values: &[f64], relative_floor: f64) -> Self {
+        let differences = values
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .collect::<Vec<_>>();
+        let mad_sigma = if differences.is_empty() {
+            0.0
+        } else {
+            // Median absolute deviation scaled to a standard deviation, and the difference of
+            // two noisy values has twice the variance of one
+            1.4826 * statistical::median(&differences) / std::f64::consts::SQRT_2
+        };
+        let level = if values.is_empty() {
+            0.0
+        } else {
+            statistical::median(values).abs()
+        };
+        Self {
+            // Metrics that are deterministic, like sizes, would otherwise have no noise at all
+            sigma: mad_sigma
+                .max(level * relative_floor / 2.0)
+                .max(f64::EPSILON),
+            relative_floor,
+        }
+    }
+
+    /// Estimates the noise of a metric, with a floor depending on what it measures.
+    pub fn for_metric(key: &MetricKey, values: &[f64]) -> Self {
+        let relative_floor = match key.unit() {
+            Unit::Bytes | Unit::Count => 0.001,
+            Unit::Nanoseconds | Unit::Milliseconds | Unit::Fps | Unit::Percent => 0.01,
+        };
+        Self::estimate(values, relative_floor)
+    }
+
+    /// Compares two single measurements, like the values of a metric on two commits.
+    pub fn compare(&self, before: f64, after: f64) -> Difference {
+        let magnitude = if before != 0.0 {
+            (after - before) / before.abs()
+        } else {
+            0.0
+        };
+        // Both values are noisy
+        let t = (after - before).abs() / (self.sigma * std::f64::consts::SQRT_2);
+        let confidence = 1.0 - erfc(t / std::f64::consts::SQRT_2).min(1.0);
+        Difference {
+            before,
+            after,
+            magnitude,
+            confidence,
+            significant: confidence >= SIGNIFICANCE && magnitude.abs() >= self.relative_floor,
+        }
+    }
+}
+
+/// Confidence above which a change is considered real.
+pub const SIGNIFICANCE: f64 = 0.99;
+
+/// Change of a metric between two measurements.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Difference {
+    pub before: f64,
+    pub after: f64,
+    /// Relative change, `0.1` is 10% higher
+    pub magnitude: f64,
+    /// From 0 to 1, how unlikely it is that the change is just noise
+    pub confidence: f64,
+    /// Whether the change is both confident and larger than the floor of the noise model
+    pub significant: bool,
+}
+
+/// A commit after which a metric changed.
+#[derive(Serialize, Debug, Clone)]
+pub struct ChangePoint {
+    /// Position in the series of the first sample after the change
+    pub index: usize,
+    /// First commit measured after the change
+    pub commit: String,
+    pub timestamp: u128,
+    /// Mean of the segment before the change
+    pub before: f64,
+    /// Mean of the segment after the change
+    pub after: f64,
+    /// Relative change, `0.1` is 10% higher
+    pub magnitude: f64,
+    /// From 0 to 1, how unlikely it is that the change is just noise
+    pub confidence: f64,
+}
+
+/// Finds the commits where the mean of a series changed, using PELT (Killick et al., 2012)
+/// with a Gaussian cost whose variance comes from `noise`.
+///
+/// Values first go through a running median of three, so that a single outlier is not reported
+/// as a change. Segments have at least two samples, so a change on the latest commit is only
+/// detected once another commit has been measured.
+pub fn detect(series: &[Sample], noise: &NoiseModel) -> Vec<ChangePoint> {
+    const MIN_SEGMENT: usize = 2;
+
+    let n = series.len();
+    if n < 2 * MIN_SEGMENT {
+        return vec![];
+    }
+    let values = (0..n)
+        .map(|i| {
+            if i == 0 || i == n - 1 {
+                return series[i].value;
+            }
+            let mut window = [series[i - 1].value, series[i].value, series[i + 1].value];
+            window.sort_by(f64::total_cmp);
+            window[1]
+        })
+        .collect::<Vec<_>>();
+
+    let variance = noise.sigma * noise.sigma;
+    let mut sum = vec![0.0; n + 1];
+    let mut sum_squares = vec![0.0; n + 1];
+    for (i, value) in values.iter().enumerate() {
+        sum[i + 1] = sum[i] + value;
+        sum_squares[i + 1] = sum_squares[i] + value * value;
+    }
+    let mean = |start: usize, end: usize| (sum[end] - sum[start]) / (end - start) as f64;
+    let cost = |start: usize, end: usize| {
+        let length = (end - start) as f64;
+        let segment_sum = sum[end] - sum[start];
+        ((sum_squares[end] - sum_squares[start]) - segment_sum * segment_sum / length).max(0.0)
+            / variance
+    };
+    // Bayesian information criterion, for a change of mean with a known variance
+    let penalty = 2.0 * (n as f64).ln();
+
+    // Optimal cost of the series up to each position, and where its last segment starts
+    let mut best = vec![f64::INFINITY; n + 1];
+    let mut last_start = vec![0; n + 1];
+    best[0] = -penalty;
+    let mut candidates = vec![0];
+    for end in MIN_SEGMENT..=n {
+        if end >= 2 * MIN_SEGMENT {
+            candidates.push(end - MIN_SEGMENT);
+        }
+        let costs = candidates
+            .iter()
+            .map(|&start| (start, best[start] + cost(start, end) + penalty))
+            .collect::<Vec<_>>();
+        let (start, total) = costs
+            .iter()
+            .copied()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        best[end] = total;
+        last_start[end] = start;
+        // Starts that can't be optimal for this end won't be for any later one
+        candidates = costs
+            .into_iter()
+            .filter(|(_, total)| total - penalty <= best[end])
+            .map(|(start, _)| start)
+            .collect();
+    }
+
+    let mut boundaries = vec![n];
+    let mut end = n;
+    while end > 0 {
+        end = last_start[end];
+        boundaries.push(end);
+    }
+    boundaries.reverse();
+
+    boundaries
+        .windows(3)
+        .filter_map(|segments| {
+            let (start, change, end) = (segments[0], segments[1], segments[2]);
+            let before = mean(start, change);
+            let after = mean(change, end);
+            let magnitude = if before != 0.0 {
+                (after - before) / before.abs()
+            } else {
+                0.0
+            };
+            if magnitude.abs() < noise.relative_floor {
+                return None;
+            }
+            let standard_error =
+                noise.sigma * (1.0 / (change - start) as f64 + 1.0 / (end - change) as f64).sqrt();
+            let t = (after - before).abs() / standard_error;
+            Some(ChangePoint {
+                index: change,
+                commit: series[change].commit.clone(),
+                timestamp: series[change].timestamp,
+                before,
+                after,
+                magnitude,
+                confidence: 1.0 - erfc(t / std::f64::consts::SQRT_2).min(1.0),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values around `level`, with a deterministic noise of up to one percent.
+    fn noisy(level: f64, len: usize, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let noise = (state >> 33) as f64 / (1u64 << 31) as f64 * 2.0 - 1.0;
+                level * (1.0 + noise * 0.01)
+            })
+            .collect()
+    }
+
+    fn series(values: &[f64]) -> Vec<Sample> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| Sample {
+                commit: format!("commit-{i}"),
+                timestamp: i as u128,
+                value,
+            })
+            .collect()
+    }
+
+    fn detect_values(values: &[f64]) -> Vec<ChangePoint> {
+        detect(&series(values), &NoiseModel::estimate(values, 0.01))
+    }
+
+    #[test]
+    fn clean_step() {
+        let mut values = noisy(100.0, 20, 1);
+        values.extend(noisy(110.0, 20, 2));
+        let changes = detect_values(&values);
+        assert_eq!(changes.len(), 1, "{changes:?}");
+        assert_eq!(changes[0].index, 20);
+        assert_eq!(changes[0].commit, "commit-20");
+        assert!((changes[0].magnitude - 0.1).abs() < 0.01, "{changes:?}");
+        assert!(changes[0].confidence > SIGNIFICANCE);
+    }
+
+    #[test]
+    fn pure_noise() {
+        let changes = detect_values(&noisy(100.0, 60, 3));
+        assert!(changes.is_empty(), "{changes:?}");
+    }
+
+    #[test]
+    fn single_outlier() {
+        let mut values = noisy(100.0, 30, 4);
+        values[15] = 150.0;
+        let changes = detect_values(&values);
+        assert!(changes.is_empty(), "{changes:?}");
+    }
+
+    #[test]
+    fn compare_around_threshold() {
+        let noise = NoiseModel {
+            sigma: 1.0,
+            relative_floor: 0.01,
+        };
+        // Significant from a difference of about 2.576 * sqrt(2) = 3.643 sigmas
+        let below = noise.compare(100.0, 103.6);
+        assert!(below.confidence < SIGNIFICANCE);
+        assert!(!below.significant);
+        let above = noise.compare(100.0, 103.7);
+        assert!(above.confidence > SIGNIFICANCE);
+        assert!(above.significant);
+        assert!((above.magnitude - 0.037).abs() < 1e-9);
+        assert!(noise.compare(103.7, 100.0).significant);
+
+        // Confident, but smaller than the floor
+        let precise = NoiseModel {
+            sigma: 0.01,
+            relative_floor: 0.01,
+        };
+        let small = precise.compare(100.0, 100.5);
+        assert!(small.confidence > SIGNIFICANCE);
+        assert!(!small.significant);
+    }
+}
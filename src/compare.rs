@@ -0,0 +1,107 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use crate::{
+    metric_key::MetricKey,
+    stats::{Stats, Unit},
+};
+
+/// Threshold of metrics without enough history to estimate one, in percent.
+pub const DEFAULT_THRESHOLD: f64 = 1.0;
+
+/// Filter of the compare page a metric belongs to, `compile-time`, `stress-tests`,
+/// `benchmarks` or `other`.
+pub fn category_filter(metric: &str) -> &'static str {
+    if metric.starts_with("large-scene-fps.") || metric.starts_with("stress-test-fps.") {
+        "stress-tests"
+    } else if metric.starts_with("benchmarks.") {
+        "benchmarks"
+    } else if metric.starts_with("compile-time")
+        || metric.starts_with("crate-compile-time")
+        || metric.ends_with(".size")
+    {
+        "compile-time"
+    } else {
+        "other"
+    }
+}
+
+/// A metric of two commits, as listed on the compare page.
+#[derive(Serialize, Debug, Clone)]
+pub struct Row {
+    pub metric: String,
+    pub unit: Unit,
+    pub base: Option<f64>,
+    pub other: Option<f64>,
+    /// Relative change, in percent
+    pub change: Option<f64>,
+    /// Changes within plus or minus this percentage are noise
+    pub threshold: Option<f64>,
+    pub significant: bool,
+    /// Whether the change is an improvement, see [`MetricKey::higher_is_better`]
+    pub better: bool,
+}
+
+/// Thresholds of each metric, in percent, from how far the commits preceding `base` are from
+/// it. A change is significant above the upper quartile of those distances plus three times
+/// their interquartile range. Metrics need at least three preceding values.
+pub fn significance_thresholds(base: &Stats, preceding: &[Stats]) -> HashMap<String, f64> {
+    if preceding.len() < 3 {
+        return HashMap::new();
+    }
+    base.metrics
+        .iter()
+        .filter(|(_, value)| value.value != 0.0)
+        .filter_map(|(metric, value)| {
+            let mut changes = preceding
+                .iter()
+                .filter_map(|stats| stats.metrics.get(metric))
+                .filter(|other| other.value != 0.0)
+                .map(|other| ((other.value / value.value - 1.0) * 100.0).abs())
+                .collect::<Vec<_>>();
+            if changes.len() < 3 {
+                return None;
+            }
+            changes.sort_by(f64::total_cmp);
+            let q1 = changes[changes.len() / 4];
+            let q3 = changes[changes.len() * 3 / 4];
+            Some((metric.clone(), q3 + (q3 - q1) * 3.0))
+        })
+        .collect()
+}
+
+/// Compares every metric of two commits, sorted by name.
+pub fn compare(base: &Stats, other: &Stats, thresholds: &HashMap<String, f64>) -> Vec<Row> {
+    base.metrics
+        .keys()
+        .chain(other.metrics.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|metric| {
+            let base_value = base.metrics.get(metric);
+            let other_value = other.metrics.get(metric);
+            let change = match (base_value, other_value) {
+                (Some(base), Some(other)) if base.value != 0.0 => {
+                    Some((other.value / base.value - 1.0) * 100.0)
+                }
+                _ => None,
+            };
+            let threshold = thresholds.get(metric).copied();
+            let higher_is_better = metric
+                .parse::<MetricKey>()
+                .is_ok_and(|key| key.higher_is_better());
+            Row {
+                metric: metric.clone(),
+                unit: base_value.or(other_value).unwrap().unit,
+                base: base_value.map(|value| value.value),
+                other: other_value.map(|value| value.value),
+                change,
+                threshold,
+                significant: change
+                    .is_some_and(|change| change.abs() > threshold.unwrap_or(DEFAULT_THRESHOLD)),
+                better: change.is_some_and(|change| (change > 0.0) == higher_is_better),
+            }
+        })
+        .collect()
+}
@@ -1,14 +1,22 @@
 use std::{collections::HashMap, path::PathBuf};
 
+use crate::stats::Value;
+
+pub mod benchmark_ids;
+pub mod change_points;
+pub mod compare;
+pub mod metric_key;
 pub mod metrics;
+pub mod samples;
 pub mod stats;
+pub mod store;
 
 pub trait Metrics: std::fmt::Debug {
     fn prepare(&self) -> bool;
     fn artifacts(&self) -> HashMap<String, PathBuf> {
         HashMap::new()
     }
-    fn collect(&self) -> HashMap<String, u64>;
+    fn collect(&self) -> HashMap<String, Value>;
 }
 
 pub fn file_safe_metric_name(metric: &str) -> String {
@@ -0,0 +1,477 @@
+use std::{fmt, str::FromStr};
+
+use serde::Serialize;
+
+use crate::stats::Unit;
+
+/// Families whose first segment ends with the platform, like `compile-time-unix-x86_64-16`.
+const PLATFORM_FAMILIES: [&str; 4] = [
+    "crate-compile-time",
+    "compile-time",
+    "doc-build-time",
+    "native",
+];
+
+/// Families of runtime metrics, like `stress-test-fps.many_cubes.params-benchmark.cpu_usage.mean`.
+const RUNTIME_FAMILIES: [&str; 2] = ["stress-test-fps", "large-scene-fps"];
+
+/// What a runtime metric measures, when it's not the frame rate.
+const RUNTIME_DIMENSIONS: [&str; 4] = ["frame_time", "cpu_usage", "gpu_usage", "gpu_memory"];
+
+/// Name of a metric in [`Stats::metrics`](crate::stats::Stats::metrics).
+///
+/// Keys are dot separated, the first segment being the family and the last one the statistic.
+/// The subject can contain dots (benchmark ids often do), so it is everything that's left once
+/// the other parts have been identified.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MetricKey {
+    /// `compile-time`, `stress-test-fps`, `benchmarks`, ...
+    pub family: String,
+    /// Platform of a build metric, or parameters of a runtime metric
+    pub variant: Option<String>,
+    /// Crate, stress test, benchmark id, ...
+    pub subject: Option<String>,
+    /// Measured quantity of a runtime metric, like `cpu_usage`, the frame rate if `None`
+    pub dimension: Option<String>,
+    /// `mean`, `median`, `size`, ...
+    pub statistic: String,
+}
+
+impl MetricKey {
+    pub fn new(family: impl Into<String>, statistic: impl Into<String>) -> Self {
+        Self {
+            family: family.into(),
+            variant: None,
+            subject: None,
+            dimension: None,
+            statistic: statistic.into(),
+        }
+    }
+
+    pub fn with_variant(mut self, variant: impl Into<String>) -> Self {
+        self.variant = Some(variant.into());
+        self
+    }
+
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn with_dimension(mut self, dimension: impl Into<String>) -> Self {
+        self.dimension = Some(dimension.into());
+        self
+    }
+
+    pub fn with_statistic(mut self, statistic: impl Into<String>) -> Self {
+        self.statistic = statistic.into();
+        self
+    }
+
+    /// Variant for metrics depending on the platform they are collected on, like
+    /// `unix-x86_64-16` when built with 16 jobs.
+    pub fn platform(nb_jobs: Option<u32>) -> String {
+        let platform = format!("{}-{}", std::env::consts::FAMILY, std::env::consts::ARCH);
+        match nb_jobs {
+            Some(nb_jobs) => format!("{platform}-{nb_jobs}"),
+            None => platform,
+        }
+    }
+
+    pub fn is_runtime(&self) -> bool {
+        RUNTIME_FAMILIES.contains(&self.family.as_str())
+    }
+
+    pub fn unit(&self) -> Unit {
+        if self.statistic == "size" {
+            return Unit::Bytes;
+        }
+        match self.family.as_str() {
+            "compile-time" | "crate-compile-time" | "doc-build-time" => Unit::Milliseconds,
+            "stress-test-fps" | "large-scene-fps" => match self.dimension.as_deref() {
+                _ if self.statistic == "duration" => Unit::Milliseconds,
+                _ if self.statistic == "frames" => Unit::Count,
+                None => Unit::Fps,
+                Some("frame_time") => Unit::Milliseconds,
+                Some(_) => Unit::Percent,
+            },
+            "benchmarks" if !self.is_instruction_count() => Unit::Nanoseconds,
+            "tests"
+                if self.statistic == "duration" || self.subject.as_deref() == Some("slowest") =>
+            {
+                Unit::Milliseconds
+            }
+            "examples" if self.statistic == "build_time" => Unit::Milliseconds,
+            _ => Unit::Count,
+        }
+    }
+
+    /// Whether an increase of the metric is an improvement, like for frame rates or the number
+    /// of passing tests.
+    pub fn higher_is_better(&self) -> bool {
+        match self.family.as_str() {
+            "tests" => self.statistic == "passed",
+            "examples" => matches!(self.statistic.as_str(), "succeeded" | "success"),
+            _ => self.unit() == Unit::Fps,
+        }
+    }
+
+    /// Whether changes of the metric are worth reporting. Statistics describing the spread of
+    /// measurements, their extremes or the bounds of an estimate are too noisy, the CPU time of
+    /// a build is followed through its wall time, and runtime metrics are only followed through
+    /// their mean.
+    pub fn is_tracked(&self) -> bool {
+        if self.is_runtime() {
+            return self.statistic == "mean";
+        }
+        // Crate compile times have the same statistics for the metadata, like `rmeta-std_dev`
+        let statistic = self
+            .statistic
+            .strip_prefix("rmeta-")
+            .unwrap_or(&self.statistic);
+        !(matches!(
+            statistic,
+            "std_dev"
+                | "stddev"
+                | "median_abs_dev"
+                | "min"
+                | "max"
+                | "fastest"
+                | "slowest"
+                | "user"
+                | "system"
+        ) || statistic.ends_with("_lower")
+            || statistic.ends_with("_upper"))
+    }
+
+    /// What integer values were multiplied by when they were stored without a unit.
+    pub(crate) fn legacy_scale(&self) -> f64 {
+        match self.family.as_str() {
+            "stress-test-fps" | "large-scene-fps"
+                if self.statistic != "duration" && self.statistic != "frames" =>
+            {
+                1000.0
+            }
+            // Stored in picoseconds
+            "benchmarks" if !self.is_instruction_count() => 1000.0,
+            _ => 1.0,
+        }
+    }
+
+    fn is_instruction_count(&self) -> bool {
+        matches!(
+            self.statistic.as_str(),
+            "instructions" | "l1_misses" | "ll_misses" | "estimated_cycles"
+        )
+    }
+
+    /// The same metric with another statistic, to go from `mean` to `median`.
+    pub fn sibling(&self, statistic: &str) -> Self {
+        self.clone().with_statistic(statistic)
+    }
+}
+
+impl fmt::Display for MetricKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.family)?;
+        if self.is_runtime() {
+            if let Some(subject) = &self.subject {
+                write!(f, ".{subject}")?;
+            }
+            if let Some(variant) = &self.variant {
+                write!(f, ".{variant}")?;
+            }
+            if let Some(dimension) = &self.dimension {
+                write!(f, ".{dimension}")?;
+            }
+        } else {
+            if let Some(variant) = &self.variant {
+                write!(f, "-{variant}")?;
+            }
+            if let Some(subject) = &self.subject {
+                write!(f, ".{subject}")?;
+            }
+        }
+        write!(f, ".{}", self.statistic)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMetricKeyError(pub String);
+
+impl fmt::Display for ParseMetricKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid metric key: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMetricKeyError {}
+
+impl FromStr for MetricKey {
+    type Err = ParseMetricKeyError;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        let error = || ParseMetricKeyError(key.to_string());
+
+        let (first, rest) = key.split_once('.').ok_or_else(error)?;
+        let (middle, statistic) = match rest.rsplit_once('.') {
+            Some((middle, statistic)) => (Some(middle), statistic),
+            None => (None, rest),
+        };
+        if first.is_empty() || statistic.is_empty() {
+            return Err(error());
+        }
+
+        if RUNTIME_FAMILIES.contains(&first) {
+            let middle = middle.ok_or_else(error)?;
+            let (subject, rest) = match middle.split_once('.') {
+                Some((subject, rest)) => (subject, Some(rest)),
+                None => (middle, None),
+            };
+            if subject.is_empty() {
+                return Err(error());
+            }
+            let mut metric_key = MetricKey::new(first, statistic).with_subject(subject);
+            // Parameter values can contain dots, like `params-scale-1.5`, so the dimension is
+            // taken from the right and everything between it and the subject is the variant
+            let (variant, dimension) = match rest {
+                None => (None, None),
+                Some(rest) if RUNTIME_DIMENSIONS.contains(&rest) => (None, Some(rest)),
+                Some(rest) => match rest.rsplit_once('.') {
+                    Some((variant, dimension)) if RUNTIME_DIMENSIONS.contains(&dimension) => {
+                        (Some(variant), Some(dimension))
+                    }
+                    _ => (Some(rest), None),
+                },
+            };
+            if let Some(variant) = variant {
+                if !variant.starts_with("params") {
+                    return Err(error());
+                }
+                metric_key.variant = Some(variant.to_string());
+            }
+            metric_key.dimension = dimension.map(|dimension| dimension.to_string());
+            return Ok(metric_key);
+        }
+
+        let mut metric_key = PLATFORM_FAMILIES
+            .iter()
+            .find_map(|family| {
+                let variant = first.strip_prefix(family)?.strip_prefix('-')?;
+                Some(MetricKey::new(*family, statistic).with_variant(variant))
+            })
+            .unwrap_or_else(|| MetricKey::new(first, statistic));
+        metric_key.subject = middle.map(|m| m.to_string());
+        Ok(metric_key)
+    }
+}
+
+impl TryFrom<&str> for MetricKey {
+    type Error = ParseMetricKeyError;
+
+    fn try_from(key: &str) -> Result<Self, Self::Error> {
+        key.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(key: &str) -> MetricKey {
+        let parsed: MetricKey = key.parse().unwrap();
+        assert_eq!(parsed.to_string(), key);
+        parsed
+    }
+
+    #[test]
+    fn build_metrics() {
+        let key = round_trip("compile-time-unix-x86_64-16.mean");
+        assert_eq!(key.family, "compile-time");
+        assert_eq!(key.variant.as_deref(), Some("unix-x86_64-16"));
+        assert_eq!(key.subject, None);
+
+        let key = round_trip("crate-compile-time-unix-x86_64-16.bevy_pbr.rmeta-mean");
+        assert_eq!(key.family, "crate-compile-time");
+        assert_eq!(key.subject.as_deref(), Some("bevy_pbr"));
+        assert_eq!(key.statistic, "rmeta-mean");
+
+        let key = round_trip("native-unix-x86_64.size");
+        assert_eq!(key.family, "native");
+        assert_eq!(key.variant.as_deref(), Some("unix-x86_64"));
+
+        let key = round_trip("wasm32-unknown-unknown.optimized.size");
+        assert_eq!(key.family, "wasm32-unknown-unknown");
+        assert_eq!(key.variant, None);
+        assert_eq!(key.subject.as_deref(), Some("optimized"));
+    }
+
+    #[test]
+    fn runtime_metrics() {
+        let key = round_trip("stress-test-fps.many_cubes.params-benchmark.cpu_usage.mean");
+        assert_eq!(key.family, "stress-test-fps");
+        assert_eq!(key.subject.as_deref(), Some("many_cubes"));
+        assert_eq!(key.variant.as_deref(), Some("params-benchmark"));
+        assert_eq!(key.dimension.as_deref(), Some("cpu_usage"));
+        assert_eq!(key.statistic, "mean");
+
+        let key = round_trip("large-scene-fps.bistro.params.mean");
+        assert_eq!(key.variant.as_deref(), Some("params"));
+        assert_eq!(key.dimension, None);
+
+        assert!(
+            "stress-test-fps.many_cubes.cpu_load.mean"
+                .parse::<MetricKey>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn dotted_params() {
+        let key = round_trip("stress-test-fps.many_lights.params-scale-1.5.frame_time.mean");
+        assert_eq!(key.subject.as_deref(), Some("many_lights"));
+        assert_eq!(key.variant.as_deref(), Some("params-scale-1.5"));
+        assert_eq!(key.dimension.as_deref(), Some("frame_time"));
+
+        let key = round_trip("large-scene-fps.bistro.params-ratio-0.25-lights.mean");
+        assert_eq!(key.variant.as_deref(), Some("params-ratio-0.25-lights"));
+        assert_eq!(key.dimension, None);
+    }
+
+    #[test]
+    fn subjects_with_dots() {
+        let key = round_trip("benchmarks.ecs::iteration/iter_simple/1.5.mean_lower");
+        assert_eq!(key.family, "benchmarks");
+        assert_eq!(
+            key.subject.as_deref(),
+            Some("ecs::iteration/iter_simple/1.5")
+        );
+        assert_eq!(key.statistic, "mean_lower");
+
+        let key = round_trip("llvm-lines.<f32 as core::ops::Add>::add.lines");
+        assert_eq!(key.subject.as_deref(), Some("<f32 as core::ops::Add>::add"));
+    }
+
+    #[test]
+    fn built_keys_round_trip() {
+        let key = MetricKey::new("stress-test-fps", "mean")
+            .with_subject("bevymark")
+            .with_variant("params-waves-60")
+            .with_dimension("frame_time");
+        assert_eq!(
+            key.to_string(),
+            "stress-test-fps.bevymark.params-waves-60.frame_time.mean"
+        );
+        assert_eq!(key.to_string().parse::<MetricKey>().unwrap(), key);
+
+        let key = MetricKey::new("compile-time", "mean").with_variant("unix-x86_64-8");
+        assert_eq!(key.to_string(), "compile-time-unix-x86_64-8.mean");
+        assert_eq!(key.to_string().parse::<MetricKey>().unwrap(), key);
+    }
+
+    #[test]
+    fn higher_is_better() {
+        let higher_is_better = |key: &str| key.parse::<MetricKey>().unwrap().higher_is_better();
+        assert!(higher_is_better("stress-test-fps.bevymark.params.mean"));
+        assert!(higher_is_better("tests.bevy_ecs.passed"));
+        assert!(higher_is_better("examples.total.succeeded"));
+        assert!(higher_is_better("examples.breakout.success"));
+
+        assert!(!higher_is_better(
+            "stress-test-fps.bevymark.params.frame_time.mean"
+        ));
+        assert!(!higher_is_better(
+            "stress-test-fps.bevymark.params.cpu_usage.mean"
+        ));
+        assert!(!higher_is_better("tests.bevy_ecs.failed"));
+        assert!(!higher_is_better("tests.bevy_ecs.duration"));
+        assert!(!higher_is_better("examples.total.failed"));
+        assert!(!higher_is_better("examples.breakout.build_time"));
+        assert!(!higher_is_better("compile-time-unix-x86_64-16.mean"));
+        assert!(!higher_is_better("wasm32-unknown-unknown.size"));
+    }
+
+    #[test]
+    fn tracked_compile_times() {
+        fn tracked<'a>(family: &str, statistics: &[&'a str]) -> Vec<&'a str> {
+            statistics
+                .iter()
+                .filter(|statistic| {
+                    format!("{family}.{statistic}")
+                        .parse::<MetricKey>()
+                        .unwrap()
+                        .is_tracked()
+                })
+                .copied()
+                .collect()
+        }
+        assert_eq!(
+            tracked(
+                "compile-time-unix-x86_64-16",
+                &["mean", "stddev", "median", "user", "system", "min", "max"]
+            ),
+            ["mean", "median"]
+        );
+        assert_eq!(
+            tracked(
+                "crate-compile-time-unix-x86_64-16.bevy_ecs",
+                &[
+                    "mean",
+                    "median",
+                    "min",
+                    "max",
+                    "std_dev",
+                    "rmeta-mean",
+                    "rmeta-median",
+                    "rmeta-min",
+                    "rmeta-max",
+                    "rmeta-std_dev",
+                ]
+            ),
+            ["mean", "median", "rmeta-mean", "rmeta-median"]
+        );
+        assert_eq!(
+            tracked(
+                "doc-build-time-unix-x86_64.clean",
+                &["mean", "stddev", "median", "min", "max"]
+            ),
+            ["mean", "median"]
+        );
+    }
+
+    #[test]
+    fn units() {
+        let unit = |key: &str| key.parse::<MetricKey>().unwrap().unit();
+        assert_eq!(unit("compile-time-unix-x86_64-16.mean"), Unit::Milliseconds);
+        assert_eq!(unit("wasm32-unknown-unknown.size"), Unit::Bytes);
+        assert_eq!(unit("stress-test-fps.bevymark.params.mean"), Unit::Fps);
+        assert_eq!(
+            unit("stress-test-fps.bevymark.params.frame_time.mean"),
+            Unit::Milliseconds
+        );
+        assert_eq!(
+            unit("stress-test-fps.bevymark.params.cpu_usage.mean"),
+            Unit::Percent
+        );
+        assert_eq!(
+            unit("stress-test-fps.bevymark.params.duration"),
+            Unit::Milliseconds
+        );
+        assert_eq!(
+            unit("benchmarks.ecs::spawn/world_spawn.mean"),
+            Unit::Nanoseconds
+        );
+        assert_eq!(
+            unit("benchmarks.ecs::spawn/world_spawn.instructions"),
+            Unit::Count
+        );
+        assert_eq!(unit("dependencies.total.count"), Unit::Count);
+    }
+
+    #[test]
+    fn invalid_keys() {
+        assert!("".parse::<MetricKey>().is_err());
+        assert!("nodot".parse::<MetricKey>().is_err());
+        assert!("family.".parse::<MetricKey>().is_err());
+    }
+}
@@ -3,125 +3,286 @@ use std::{
     fs::File,
     io::BufWriter,
     path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use serde::Deserialize;
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{
+    Metrics,
+    benchmark_ids::fnv1a,
+    metric_key::MetricKey,
+    samples::{
+        BenchmarkSamples, PARTIAL_SAMPLES_PREFIX, SAMPLES_FILE, SAMPLES_FOLDER, write_samples,
+    },
+    stats::{Unit, Value},
+};
+
+pub mod parsers;
+
+use parsers::{BenchmarkParser, BenchmarkRecord, CriterionParser, Harness, Message};
 
 #[derive(Debug)]
-pub struct Benchmarks;
+pub struct Benchmarks {
+    /// Bench targets to run, all of them if empty
+    pub benches: Vec<String>,
+    /// Glob patterns on benchmark ids, a benchmark is run if it matches any of them
+    pub include: Vec<String>,
+    /// Glob patterns on benchmark ids, a benchmark is skipped if it matches any of them
+    pub exclude: Vec<String>,
+    pub clean: bool,
+    /// When the run started, in milliseconds since the epoch, to order the samples of partial
+    /// runs
+    pub started: u128,
+}
 
-impl Metrics for Benchmarks {
-    fn prepare(&self) -> bool {
-        let sh = Shell::new().unwrap();
-        sh.change_dir("benches");
-        cmd!(sh, "cargo clean").run().unwrap();
-        let out = cmd!(sh, "cargo criterion --message-format json")
+impl Benchmarks {
+    pub fn on(
+        benches: Vec<String>,
+        include: Vec<String>,
+        exclude: Vec<String>,
+        no_clean: bool,
+    ) -> Self {
+        let mut benchmarks = Self {
+            benches,
+            include,
+            exclude,
+            clean: false,
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+        };
+        // Only a full run needs to start from scratch
+        benchmarks.clean = !no_clean && !benchmarks.is_partial();
+        benchmarks
+    }
+
+    pub fn is_partial(&self) -> bool {
+        !self.benches.is_empty() || !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Identifies the selection of a partial run, so that runs with different filters on the
+    /// same commit keep their own files.
+    fn selection_hash(&self) -> String {
+        let selection = format!("{:?} {:?} {:?}", self.benches, self.include, self.exclude);
+        format!("{:016x}", fnv1a(selection.as_bytes()))
+    }
+
+    fn stats_file(&self) -> String {
+        if self.is_partial() {
+            format!("benchmarks-partial-{}.json", self.selection_hash())
+        } else {
+            "benchmarks.json".to_string()
+        }
+    }
+
+    fn samples_file(&self) -> String {
+        if self.is_partial() {
+            format!(
+                "{PARTIAL_SAMPLES_PREFIX}-{}-{}.json.gz",
+                self.started,
+                self.selection_hash()
+            )
+        } else {
+            SAMPLES_FILE.to_string()
+        }
+    }
+
+    fn is_selected(&self, id: &str) -> bool {
+        let to_regex = |patterns: &[String]| {
+            let patterns = patterns
+                .iter()
+                .map(|p| regex::escape(p).replace("\\*", ".*"))
+                .collect::<Vec<_>>();
+            Regex::new(&format!("^({})$", patterns.join("|"))).unwrap()
+        };
+        (self.include.is_empty() || to_regex(&self.include).is_match(id))
+            && (self.exclude.is_empty() || !to_regex(&self.exclude).is_match(id))
+    }
+
+    /// Lists the criterion ids of the given bench targets and keeps the ones matching the
+    /// filters, as a regex matching exactly those ids.
+    fn filter_regex(&self, sh: &Shell, benches: &[String]) -> Option<String> {
+        if self.include.is_empty() && self.exclude.is_empty() {
+            return None;
+        }
+
+        let benches = benches.iter().flat_map(|b| ["--bench", b.as_str()]);
+        let list = cmd!(sh, "cargo bench {benches...} -- --list")
             .read()
             .unwrap();
-        let benchmarks = out
+        let ids = list
+            .lines()
+            .filter_map(|line| line.strip_suffix(": benchmark"))
+            .filter(|id| self.is_selected(id))
+            .map(regex::escape)
+            .collect::<Vec<_>>();
+        Some(format!("^({})$", ids.join("|")))
+    }
+
+    /// Bench targets declared in the manifest of the benches crate, with their harness.
+    fn bench_targets(&self, sh: &Shell) -> Vec<(String, Harness)> {
+        let Ok(manifest) = sh.read_file("Cargo.toml") else {
+            return vec![];
+        };
+        let Ok(manifest) = toml::from_str::<Manifest>(&manifest) else {
+            return vec![];
+        };
+        manifest
+            .bench
+            .into_iter()
+            .filter(|bench| self.benches.is_empty() || self.benches.contains(&bench.name))
+            .map(|bench| {
+                let path = bench
+                    .path
+                    .unwrap_or_else(|| format!("benches/{}.rs", bench.name));
+                let source = sh.read_file(path).unwrap_or_default();
+                let harness = Harness::detect(&source, bench.harness);
+                (bench.name, harness)
+            })
+            .collect()
+    }
+
+    fn run_criterion(&self, sh: &Shell, benches: &[String]) -> Vec<BenchmarkRecord> {
+        let filter = self.filter_regex(sh, benches);
+        if filter.as_deref() == Some("^()$") {
+            // Nothing matched the filters
+            return vec![];
+        }
+        let filter = filter.iter().flat_map(|f| ["--", f.as_str()]);
+        let benches = benches.iter().flat_map(|b| ["--bench", b.as_str()]);
+        let out = cmd!(
+            sh,
+            "cargo criterion {benches...} --message-format json {filter...}"
+        )
+        .read()
+        .unwrap();
+
+        // Criterion's own data is lost on the next `cargo clean`, keep the raw samples around
+        let samples = out
             .lines()
             .filter(|line| {
-                serde_json::from_str::<Message>(line).unwrap().reason == "benchmark-complete"
+                serde_json::from_str::<Message>(line)
+                    .is_ok_and(|message| message.reason == "benchmark-complete")
             })
-            .map(|line| serde_json::from_str::<Benchmark>(line).unwrap())
+            .filter_map(|line| serde_json::from_str::<BenchmarkSamples>(line).ok())
             .collect::<Vec<_>>();
+        write_samples(Path::new(&self.samples_file()), &samples).unwrap();
+
+        CriterionParser.parse(&out)
+    }
+}
+
+impl Metrics for Benchmarks {
+    fn prepare(&self) -> bool {
+        let sh = Shell::new().unwrap();
+        sh.change_dir("benches");
+        if self.clean {
+            cmd!(sh, "cargo clean").run().unwrap();
+        }
 
-        let file = File::create("benchmarks.json").unwrap();
+        // Criterion doesn't run when no target or benchmark of its own is selected, but the
+        // samples are still listed in the artifacts
+        write_samples(Path::new(&self.samples_file()), &[]).unwrap();
+
+        let targets = self.bench_targets(&sh);
+        let mut benchmarks = if targets.iter().all(|(_, h)| *h == Harness::Criterion) {
+            // Let cargo criterion pick the targets when there is nothing else to run
+            self.run_criterion(&sh, &self.benches)
+        } else {
+            let criterion_targets = targets
+                .iter()
+                .filter(|(_, h)| *h == Harness::Criterion)
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            if criterion_targets.is_empty() {
+                vec![]
+            } else {
+                self.run_criterion(&sh, &criterion_targets)
+            }
+        };
+
+        for (name, harness) in targets.iter().filter(|(_, h)| *h != Harness::Criterion) {
+            let Ok(out) = cmd!(sh, "cargo bench --bench {name}").read() else {
+                continue;
+            };
+            benchmarks.extend(
+                harness
+                    .parser()
+                    .parse(&out)
+                    .into_iter()
+                    .filter(|record| self.is_selected(&record.id)),
+            );
+        }
+
+        if benchmarks.is_empty() {
+            return false;
+        }
+
+        let file = File::create(self.stats_file()).unwrap();
         let mut writer = BufWriter::new(file);
         serde_json::to_writer(&mut writer, &benchmarks).unwrap();
         true
     }
 
     fn artifacts(&self) -> HashMap<String, PathBuf> {
-        HashMap::from([(
-            "benchmarks.stats".to_string(),
-            Path::new("benchmarks.json").to_path_buf(),
-        )])
+        HashMap::from([
+            (
+                "benchmarks.stats".to_string(),
+                PathBuf::from(self.stats_file()),
+            ),
+            (
+                SAMPLES_FOLDER.to_string(),
+                PathBuf::from(self.samples_file()),
+            ),
+        ])
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
-        let timings: Vec<Benchmark> =
-            serde_json::from_reader(std::fs::File::open("benchmarks.json").unwrap()).unwrap();
-        timings
+    fn collect(&self) -> HashMap<String, Value> {
+        let records: Vec<BenchmarkRecord> =
+            serde_json::from_reader(std::fs::File::open(self.stats_file()).unwrap()).unwrap();
+        records
             .iter()
-            .flat_map(|benchmark| {
-                let bench_id = benchmark.id.clone();
-
-                ["mean", "slope", "median", "typical", "median_abs_dev"]
-                    .iter()
-                    .filter_map(|metric| match *metric {
-                        "slope" => benchmark.slope.as_ref().map(|slope| (metric, slope)),
-                        "mean" => Some((metric, &benchmark.mean)),
-                        "median" => Some((metric, &benchmark.median)),
-                        "typical" => Some((metric, &benchmark.typical)),
-                        "median_abs_dev" => Some((metric, &benchmark.median_abs_dev)),
-                        _ => unreachable!(),
-                    })
-                    .flat_map(move |(metric, timings)| {
-                        vec![
-                            (
-                                format!("benchmarks.{bench_id}.{metric}"),
-                                timings.estimate(),
-                            ),
-                            (
-                                format!("benchmarks.{bench_id}.{metric}_lower"),
-                                timings.lower_bound(),
-                            ),
-                            (
-                                format!("benchmarks.{bench_id}.{metric}_upper"),
-                                timings.upper_bound(),
-                            ),
-                        ]
-                    })
+            .filter_map(|record| {
+                let key = MetricKey::new("benchmarks", record.statistic.as_str())
+                    .with_subject(record.id.as_str());
+                let Some(estimate) = record.nanoseconds(record.estimate) else {
+                    eprintln!("skipping {key}: unsupported unit {}", record.unit);
+                    return None;
+                };
+                Some((
+                    key.to_string(),
+                    Value::new(estimate, Unit::Nanoseconds).with_bounds(
+                        record
+                            .lower_bound
+                            .and_then(|lower| record.nanoseconds(lower)),
+                        record
+                            .upper_bound
+                            .and_then(|upper| record.nanoseconds(upper)),
+                    ),
+                ))
             })
             .collect()
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Message {
-    reason: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Benchmark {
-    id: String,
-    typical: Timings,
-    mean: Timings,
-    median: Timings,
-    median_abs_dev: Timings,
-    slope: Option<Timings>,
+#[derive(Deserialize, Debug)]
+struct Manifest {
+    #[serde(default)]
+    bench: Vec<BenchTarget>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Timings {
-    estimate: f32,
-    lower_bound: f32,
-    upper_bound: f32,
-    unit: String,
+#[derive(Deserialize, Debug)]
+struct BenchTarget {
+    name: String,
+    path: Option<String>,
+    #[serde(default = "default_harness")]
+    harness: bool,
 }
 
-impl Timings {
-    fn get(&self, value: f32) -> u64 {
-        match self.unit.as_str() {
-            "ns" => (value * 1_000.0) as u64,
-            "us" => (value * 1_000_000.0) as u64,
-            "ms" => (value * 1_000_000_000.0) as u64,
-            "s" => (value * 1_000_000_000_000.0) as u64,
-            _ => panic!("Unsupported unit"),
-        }
-    }
-
-    fn estimate(&self) -> u64 {
-        self.get(self.estimate)
-    }
-    fn lower_bound(&self) -> u64 {
-        self.get(self.lower_bound)
-    }
-    fn upper_bound(&self) -> u64 {
-        self.get(self.upper_bound)
-    }
+fn default_harness() -> bool {
+    true
 }
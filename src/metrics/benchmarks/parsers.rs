@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+
+/// A single statistic of a benchmark, whatever the harness that produced it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkRecord {
+    pub id: String,
+    pub group: String,
+    pub statistic: String,
+    pub unit: String,
+    pub estimate: f64,
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+}
+
+impl BenchmarkRecord {
+    /// Converts a value of this record to nanoseconds, `None` if its unit is not a duration.
+    pub fn nanoseconds(&self, value: f64) -> Option<f64> {
+        let scale = match self.unit.as_str() {
+            "ps" => 0.001,
+            "ns" => 1.0,
+            "us" | "µs" => 1_000.0,
+            "ms" => 1_000_000.0,
+            "s" => 1_000_000_000.0,
+            _ => return None,
+        };
+        Some(value * scale)
+    }
+}
+
+pub trait BenchmarkParser {
+    fn parse(&self, output: &str) -> Vec<BenchmarkRecord>;
+}
+
+/// The harness used by a bench target, detected from its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Harness {
+    Criterion,
+    Divan,
+    /// A custom harness printing one [`BenchmarkRecord`] per line as JSON
+    JsonLines,
+}
+
+impl Harness {
+    pub fn detect(source: &str, harness: bool) -> Self {
+        if source.contains("divan::main") {
+            Harness::Divan
+        } else if source.contains("criterion_main") || source.contains("criterion_group") {
+            Harness::Criterion
+        } else if !harness {
+            Harness::JsonLines
+        } else {
+            Harness::Criterion
+        }
+    }
+
+    pub fn parser(&self) -> Box<dyn BenchmarkParser> {
+        match self {
+            Harness::Criterion => Box::new(CriterionParser),
+            Harness::Divan => Box::new(DivanParser),
+            Harness::JsonLines => Box::new(JsonLinesParser),
+        }
+    }
+}
+
+/// Parses `cargo criterion --message-format json` output.
+pub struct CriterionParser;
+
+impl BenchmarkParser for CriterionParser {
+    fn parse(&self, output: &str) -> Vec<BenchmarkRecord> {
+        output
+            .lines()
+            .filter(|line| {
+                serde_json::from_str::<Message>(line)
+                    .is_ok_and(|message| message.reason == "benchmark-complete")
+            })
+            .filter_map(
+                |line| match serde_json::from_str::<CriterionBenchmark>(line) {
+                    Ok(benchmark) => Some(benchmark),
+                    Err(error) => {
+                        eprintln!("skipping criterion benchmark: {error}");
+                        None
+                    }
+                },
+            )
+            .flat_map(|benchmark| {
+                let group = benchmark
+                    .id
+                    .split('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                [
+                    ("mean", Some(benchmark.mean)),
+                    ("slope", benchmark.slope),
+                    ("median", Some(benchmark.median)),
+                    ("typical", Some(benchmark.typical)),
+                    ("median_abs_dev", Some(benchmark.median_abs_dev)),
+                ]
+                .into_iter()
+                .filter_map(move |(statistic, timings)| {
+                    timings.map(|timings| BenchmarkRecord {
+                        id: benchmark.id.clone(),
+                        group: group.clone(),
+                        statistic: statistic.to_string(),
+                        unit: timings.unit,
+                        estimate: timings.estimate,
+                        lower_bound: Some(timings.lower_bound),
+                        upper_bound: Some(timings.upper_bound),
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+/// Parses the tree table printed by divan.
+///
+/// ```text
+/// ecs               fastest       │ slowest       │ median        │ mean          │ samples │ iters
+/// ╰─ spawn                        │               │               │               │         │
+///    ├─ 10          1.234 µs      │ 5.678 µs      │ 1.5 µs        │ 1.6 µs        │ 100     │ 100
+/// ```
+pub struct DivanParser;
+
+impl BenchmarkParser for DivanParser {
+    fn parse(&self, output: &str) -> Vec<BenchmarkRecord> {
+        const STATISTICS: [&str; 4] = ["fastest", "slowest", "median", "mean"];
+
+        let mut records = vec![];
+        let mut name_width = 0;
+        let mut path: Vec<String> = vec![];
+        for line in output.lines() {
+            if !line.starts_with([' ', '├', '╰', '│']) && line.contains("fastest") {
+                // Header of a new bench target
+                let header = line.split_whitespace().next().unwrap_or_default();
+                name_width = line
+                    .find("fastest")
+                    .map(|i| line[..i].chars().count())
+                    .unwrap_or_default();
+                path = vec![header.to_string()];
+                continue;
+            }
+            let chars = line.chars().collect::<Vec<_>>();
+            if path.is_empty() || chars.len() < name_width {
+                continue;
+            }
+
+            // Tree branches also use `│`, only split the columns after the name
+            let name_column = chars[..name_width].iter().collect::<String>();
+            let columns = chars[name_width..].iter().collect::<String>();
+            let prefix = name_column
+                .chars()
+                .take_while(|c| matches!(c, ' ' | '│' | '├' | '╰' | '─'))
+                .count();
+            let name = name_column
+                .trim_start_matches([' ', '│', '├', '╰', '─'])
+                .trim();
+            if name.is_empty() {
+                // Throughput lines are printed under the timings
+                continue;
+            }
+            let depth = prefix / 3;
+            path.truncate(depth.max(1));
+            path.push(name.to_string());
+
+            let values = columns
+                .split('│')
+                .zip(STATISTICS)
+                .filter_map(|(value, statistic)| {
+                    let (value, unit) = value.trim().split_once(' ')?;
+                    let unit = if unit == "µs" { "us" } else { unit };
+                    Some((statistic, value.parse::<f64>().ok()?, unit.to_string()))
+                })
+                .collect::<Vec<_>>();
+            if values.is_empty() {
+                // Intermediate node of the tree
+                continue;
+            }
+
+            let id = path.join("/");
+            for (statistic, estimate, unit) in values {
+                records.push(BenchmarkRecord {
+                    id: id.clone(),
+                    group: path[0].clone(),
+                    statistic: statistic.to_string(),
+                    unit,
+                    estimate,
+                    lower_bound: None,
+                    upper_bound: None,
+                });
+            }
+        }
+        records
+    }
+}
+
+/// Parses lines that are already serialized [`BenchmarkRecord`], ignoring everything else.
+pub struct JsonLinesParser;
+
+impl BenchmarkParser for JsonLinesParser {
+    fn parse(&self, output: &str) -> Vec<BenchmarkRecord> {
+        output
+            .lines()
+            .filter_map(|line| serde_json::from_str::<BenchmarkRecord>(line).ok())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct Message {
+    pub(crate) reason: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct CriterionBenchmark {
+    id: String,
+    typical: Timings,
+    mean: Timings,
+    median: Timings,
+    median_abs_dev: Timings,
+    slope: Option<Timings>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct Timings {
+    estimate: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+    unit: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(records: &'a [BenchmarkRecord], id: &str, statistic: &str) -> &'a BenchmarkRecord {
+        records
+            .iter()
+            .find(|record| record.id == id && record.statistic == statistic)
+            .unwrap_or_else(|| panic!("no {statistic} for {id} in {records:?}"))
+    }
+
+    const DIVAN_OUTPUT: &str = "\
+     Running benches/ecs.rs (target/release/deps/ecs-5d1c2bd7e4f9a0c3)
+Timer precision: 41 ns
+ecs                      fastest       │ slowest       │ median        │ mean          │ samples │ iters
+├─ spawn                               │               │               │               │         │
+│  ├─ batch                            │               │               │               │         │
+│  │  ├─ 10              1.234 µs      │ 5.678 µs      │ 1.5 µs        │ 1.6 µs        │ 100     │ 400
+│  │  │                  8.103 Mitem/s │ 1.761 Mitem/s │ 6.666 Mitem/s │ 6.25 Mitem/s  │         │
+│  │  ╰─ 100             12.5 µs       │ 20.1 µs       │ 13 µs         │ 13.4 µs       │ 100     │ 100
+│  ╰─ single             45.25 ns      │ 61.3 ns       │ 46 ns         │ 47.1 ns       │ 100     │ 12800
+├─ broken                n/a           │ n/a           │ n/a           │ n/a           │ 100     │ 100
+├─ world_new             1.5 ms        │ 2.25 ms       │ 1.6 ms        │ 1.7 ms        │ 100     │ 100
+╰─ schedule_build        1.2 s         │ 1.4 s         │ 1.25 s        │ 1.3 s         │ 10      │ 10
+";
+
+    #[test]
+    fn divan_nested_groups() {
+        let records = DivanParser.parse(DIVAN_OUTPUT);
+        let ids = records
+            .iter()
+            .map(|record| record.id.as_str())
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            ids.into_iter().collect::<Vec<_>>(),
+            [
+                "ecs/schedule_build",
+                "ecs/spawn/batch/10",
+                "ecs/spawn/batch/100",
+                "ecs/spawn/single",
+                "ecs/world_new",
+            ]
+        );
+        assert!(records.iter().all(|record| record.group == "ecs"));
+        // fastest, slowest, median and mean of each benchmark
+        assert_eq!(records.len(), 5 * 4);
+    }
+
+    #[test]
+    fn divan_units() {
+        let records = DivanParser.parse(DIVAN_OUTPUT);
+        let nanoseconds = |id: &str, statistic: &str| {
+            let record = find(&records, id, statistic);
+            record.nanoseconds(record.estimate).unwrap()
+        };
+        assert_eq!(nanoseconds("ecs/spawn/single", "fastest"), 45.25);
+        assert_eq!(nanoseconds("ecs/spawn/batch/10", "median"), 1_500.0);
+        assert_eq!(nanoseconds("ecs/world_new", "slowest"), 2_250_000.0);
+        assert_eq!(nanoseconds("ecs/schedule_build", "mean"), 1_300_000_000.0);
+        // Throughput lines don't replace the timings
+        assert_eq!(find(&records, "ecs/spawn/batch/10", "fastest").unit, "us");
+    }
+
+    #[test]
+    fn divan_malformed_lines() {
+        assert!(DivanParser.parse("error: bench failed\n").is_empty());
+        // Values that are not durations are skipped along with their row
+        let records = DivanParser.parse(DIVAN_OUTPUT);
+        assert!(records.iter().all(|record| record.id != "ecs/broken"));
+    }
+
+    #[test]
+    fn criterion_messages() {
+        let output = r#"{"reason":"benchmark-complete","id":"spawn/10","report_directory":"target/criterion/reports/spawn/10","iteration_count":[10,20],"measured_values":[1210.0,2400.0],"unit":"ns","throughput":[],"typical":{"estimate":120.5,"lower_bound":119.8,"upper_bound":121.3,"unit":"ns"},"mean":{"estimate":121.0,"lower_bound":120.1,"upper_bound":122.0,"unit":"ns"},"median":{"estimate":120.2,"lower_bound":119.9,"upper_bound":120.6,"unit":"ns"},"median_abs_dev":{"estimate":0.8,"lower_bound":0.5,"upper_bound":1.1,"unit":"ns"},"slope":{"estimate":120.5,"lower_bound":119.8,"upper_bound":121.3,"unit":"ns"},"change":null}
+{"reason":"group-complete","group_name":"spawn","benchmarks":["spawn/10"],"report_directory":"target/criterion/reports/spawn"}
+Gnuplot not found, using plotters backend
+{"reason":"benchmark-complete","id":"spawn/100","unit":"ns"}
+{"reason":"benchmark-complete","id":"render/mesh","report_directory":"target/criterion/reports/render/mesh","iteration_count":[1],"measured_values":[2.5],"unit":"ms","throughput":[],"typical":{"estimate":2.5,"lower_bound":2.4,"upper_bound":2.6,"unit":"ms"},"mean":{"estimate":2.5,"lower_bound":2.4,"upper_bound":2.6,"unit":"ms"},"median":{"estimate":2.5,"lower_bound":2.4,"upper_bound":2.6,"unit":"ms"},"median_abs_dev":{"estimate":0.1,"lower_bound":0.05,"upper_bound":0.15,"unit":"ms"},"slope":null,"change":null}
+"#;
+        let records = CriterionParser.parse(output);
+        // The second benchmark is missing its estimates, and `render/mesh` has no slope
+        assert_eq!(records.len(), 5 + 4);
+
+        let mean = find(&records, "spawn/10", "mean");
+        assert_eq!(mean.group, "spawn");
+        assert_eq!(mean.estimate, 121.0);
+        assert_eq!(mean.lower_bound, Some(120.1));
+        assert_eq!(mean.upper_bound, Some(122.0));
+
+        let median = find(&records, "render/mesh", "median");
+        assert_eq!(median.group, "render");
+        assert_eq!(median.nanoseconds(median.estimate), Some(2_500_000.0));
+        assert!(
+            records
+                .iter()
+                .all(|record| record.statistic != "slope" || record.id == "spawn/10")
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let output = r#"running 2 benchmarks
+{"id":"assets/load","group":"assets","statistic":"mean","unit":"µs","estimate":12.5,"lower_bound":null,"upper_bound":null}
+{"id":"assets/load","group":"assets","statistic":"mean","unit":"ns"
+{"reason":"build-finished","success":true}
+{"id":"assets/parse","group":"assets","statistic":"median","unit":"bytes","estimate":100.0,"lower_bound":90.0,"upper_bound":110.0}
+"#;
+        let records = JsonLinesParser.parse(output);
+        assert_eq!(records.len(), 2);
+        let load = find(&records, "assets/load", "mean");
+        assert_eq!(load.nanoseconds(load.estimate), Some(12_500.0));
+        // Parsed, but skipped when collected as its unit is not a duration
+        let parse = find(&records, "assets/parse", "median");
+        assert_eq!(parse.nanoseconds(parse.estimate), None);
+    }
+}
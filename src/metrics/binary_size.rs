@@ -2,7 +2,7 @@ use std::{collections::HashMap, path::Path};
 
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{Metrics, metric_key::MetricKey, stats::Value};
 
 #[derive(Debug)]
 pub struct BinarySize {
@@ -30,17 +30,15 @@ impl Metrics for BinarySize {
             .is_ok()
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
+    fn collect(&self) -> HashMap<String, Value> {
         let target_dir = Path::new("target/release/examples");
         let file_path = target_dir.join(&self.example_name);
         let size = file_path.metadata().unwrap().len();
         HashMap::from([(
-            format!(
-                "native-{}-{}.size",
-                std::env::consts::FAMILY,
-                std::env::consts::ARCH
-            ),
-            size,
+            MetricKey::new("native", "size")
+                .with_variant(MetricKey::platform(None))
+                .to_string(),
+            Value::bytes(size),
         )])
     }
 }
@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use xshell::{Shell, cmd};
+
+use crate::{Metrics, metric_key::MetricKey, stats::Value};
+
+/// Runs criterion benchmarks once each under callgrind, to get deterministic instruction counts
+/// and simulated cache behaviour instead of noisy wall clock times.
+#[derive(Debug)]
+pub struct Callgrind {
+    /// Bench targets to run, all of them if empty
+    pub benches: Vec<String>,
+    /// Only run benchmarks whose id contains this
+    pub filter: String,
+}
+
+impl Callgrind {
+    pub fn on(benches: Vec<String>, filter: String) -> Self {
+        Self { benches, filter }
+    }
+}
+
+impl Metrics for Callgrind {
+    fn prepare(&self) -> bool {
+        let sh = Shell::new().unwrap();
+        sh.change_dir("benches");
+        let Ok(out) = cmd!(sh, "cargo bench --no-run --message-format json").read() else {
+            return false;
+        };
+        let executables = out
+            .lines()
+            .filter(|line| {
+                serde_json::from_str::<Message>(line)
+                    .is_ok_and(|message| message.reason == "compiler-artifact")
+            })
+            .filter_map(|line| serde_json::from_str::<Artifact>(line).ok())
+            .filter(|artifact| artifact.target.kind.iter().any(|k| k == "bench"))
+            .filter(|artifact| {
+                self.benches.is_empty() || self.benches.contains(&artifact.target.name)
+            })
+            .filter_map(|artifact| artifact.executable)
+            .collect::<Vec<_>>();
+
+        let mut results = vec![];
+        for executable in executables {
+            let Ok(list) = cmd!(sh, "{executable} --bench --list").read() else {
+                continue;
+            };
+            let ids = list
+                .lines()
+                .filter_map(|line| line.strip_suffix(": benchmark"))
+                .filter(|id| id.contains(&self.filter))
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>();
+
+            for id in ids {
+                let out_file = "callgrind.out";
+                // Without `--bench`, criterion runs each benchmark a single time
+                if cmd!(
+                    sh,
+                    "valgrind --tool=callgrind --cache-sim=yes --callgrind-out-file={out_file} {executable} --exact {id}"
+                )
+                .quiet()
+                .ignore_stderr()
+                .run()
+                .is_err()
+                {
+                    continue;
+                }
+                if let Some(events) = parse_events(&sh.read_file(out_file).unwrap_or_default()) {
+                    results.push(CallgrindResult { id, events });
+                }
+            }
+        }
+
+        let file = File::create("callgrind.json").unwrap();
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &results).unwrap();
+        !results.is_empty()
+    }
+
+    fn artifacts(&self) -> HashMap<String, PathBuf> {
+        HashMap::from([(
+            "callgrind.stats".to_string(),
+            Path::new("callgrind.json").to_path_buf(),
+        )])
+    }
+
+    fn collect(&self) -> HashMap<String, Value> {
+        let results: Vec<CallgrindResult> =
+            serde_json::from_reader(std::fs::File::open("callgrind.json").unwrap()).unwrap();
+        results
+            .iter()
+            .flat_map(|result| {
+                let event = |name: &str| result.events.get(name).copied().unwrap_or_default();
+                let instructions = event("Ir");
+                let accesses = instructions + event("Dr") + event("Dw");
+                let l1_misses = event("I1mr") + event("D1mr") + event("D1mw");
+                let ll_misses = event("ILmr") + event("DLmr") + event("DLmw");
+                // Same cost model as iai: 1 cycle for L1, 5 for LL and 35 for RAM
+                let l1_hits = accesses.saturating_sub(l1_misses);
+                let ll_hits = l1_misses.saturating_sub(ll_misses);
+                let estimated_cycles = l1_hits + 5 * ll_hits + 35 * ll_misses;
+
+                let key =
+                    MetricKey::new("benchmarks", "instructions").with_subject(result.id.as_str());
+                [
+                    (key.to_string(), Value::count(instructions)),
+                    (
+                        key.sibling("l1_misses").to_string(),
+                        Value::count(l1_misses),
+                    ),
+                    (
+                        key.sibling("ll_misses").to_string(),
+                        Value::count(ll_misses),
+                    ),
+                    (
+                        key.sibling("estimated_cycles").to_string(),
+                        Value::count(estimated_cycles),
+                    ),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Reads the `events:` header and the `summary:`/`totals:` line of a callgrind output file.
+fn parse_events(out: &str) -> Option<HashMap<String, u64>> {
+    let names = out
+        .lines()
+        .find_map(|line| line.strip_prefix("events:"))?
+        .split_whitespace();
+    let totals = out
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix("summary:")
+                .or_else(|| line.strip_prefix("totals:"))
+        })?
+        .split_whitespace()
+        .map(|value| value.parse::<u64>().unwrap_or_default());
+    Some(names.map(|n| n.to_string()).zip(totals).collect())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Message {
+    reason: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Artifact {
+    target: Target,
+    executable: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CallgrindResult {
+    id: String,
+    events: HashMap<String, u64>,
+}
@@ -6,7 +6,11 @@ use std::{
 use serde::Deserialize;
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{
+    Metrics,
+    metric_key::MetricKey,
+    stats::{Unit, Value},
+};
 
 #[derive(Debug)]
 pub struct CompileTime {
@@ -50,61 +54,60 @@ impl Metrics for CompileTime {
         )])
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
-        let key = format!(
-            "compile-time-{}-{}-{}",
-            std::env::consts::FAMILY,
-            std::env::consts::ARCH,
-            self.nb_jobs
-        );
+    fn collect(&self) -> HashMap<String, Value> {
+        let key = MetricKey::new("compile-time", "mean")
+            .with_variant(MetricKey::platform(Some(self.nb_jobs)));
         let results: Hyperfine = serde_json::from_reader(
             std::fs::File::open(format!("build-{}.json", self.nb_jobs)).unwrap(),
         )
         .unwrap();
         HashMap::from([
             (
-                format!("{key}.mean"),
-                (results.results[0].mean * 1000.0) as u64,
+                key.sibling("mean").to_string(),
+                Value::new(results.results[0].mean * 1000.0, Unit::Milliseconds),
             ),
             (
-                format!("{key}.stddev"),
-                (results.results[0].stddev.unwrap_or_default() * 1000.0) as u64,
+                key.sibling("stddev").to_string(),
+                Value::new(
+                    results.results[0].stddev.unwrap_or_default() * 1000.0,
+                    Unit::Milliseconds,
+                ),
             ),
             (
-                format!("{key}.median"),
-                (results.results[0].median * 1000.0) as u64,
+                key.sibling("median").to_string(),
+                Value::new(results.results[0].median * 1000.0, Unit::Milliseconds),
             ),
             (
-                format!("{key}.user"),
-                (results.results[0].user * 1000.0) as u64,
+                key.sibling("user").to_string(),
+                Value::new(results.results[0].user * 1000.0, Unit::Milliseconds),
             ),
             (
-                format!("{key}.system"),
-                (results.results[0].system * 1000.0) as u64,
+                key.sibling("system").to_string(),
+                Value::new(results.results[0].system * 1000.0, Unit::Milliseconds),
             ),
             (
-                format!("{key}.min"),
-                (results.results[0].min * 1000.0) as u64,
+                key.sibling("min").to_string(),
+                Value::new(results.results[0].min * 1000.0, Unit::Milliseconds),
             ),
             (
-                format!("{key}.max"),
-                (results.results[0].max * 1000.0) as u64,
+                key.sibling("max").to_string(),
+                Value::new(results.results[0].max * 1000.0, Unit::Milliseconds),
             ),
         ])
     }
 }
 
 #[derive(Deserialize)]
-struct Hyperfine {
-    results: Vec<HyperfineResults>,
+pub(crate) struct Hyperfine {
+    pub(crate) results: Vec<HyperfineResults>,
 }
 #[derive(Deserialize)]
-struct HyperfineResults {
-    mean: f32,
-    max: f32,
-    min: f32,
-    stddev: Option<f32>,
-    median: f32,
-    user: f32,
-    system: f32,
+pub(crate) struct HyperfineResults {
+    pub(crate) mean: f64,
+    pub(crate) max: f64,
+    pub(crate) min: f64,
+    pub(crate) stddev: Option<f64>,
+    pub(crate) median: f64,
+    pub(crate) user: f64,
+    pub(crate) system: f64,
 }
@@ -8,7 +8,11 @@ use std::{
 use serde::{Deserialize, Serialize};
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{
+    Metrics,
+    metric_key::MetricKey,
+    stats::{Unit, Value},
+};
 
 #[derive(Debug)]
 pub struct CrateCompileTime {
@@ -72,13 +76,9 @@ impl Metrics for CrateCompileTime {
         )])
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
-        let key = format!(
-            "crate-compile-time-{}-{}-{}",
-            std::env::consts::FAMILY,
-            std::env::consts::ARCH,
-            self.nb_jobs
-        );
+    fn collect(&self) -> HashMap<String, Value> {
+        let key = MetricKey::new("crate-compile-time", "mean")
+            .with_variant(MetricKey::platform(Some(self.nb_jobs)));
 
         let timings: HashMap<String, Vec<CrateTiming>> = serde_json::from_reader(
             std::fs::File::open(format!("crate-stats-{}.json", self.nb_jobs)).unwrap(),
@@ -87,57 +87,75 @@ impl Metrics for CrateCompileTime {
         timings
             .iter()
             .flat_map(|(crate_name, timings)| {
+                let key = key.clone().with_subject(crate_name.clone());
                 let durations: Vec<f64> = timings.iter().map(|timing| timing.duration).collect();
                 let rmeta_times: Vec<f64> =
                     timings.iter().map(|timing| timing.rmeta_time).collect();
                 vec![
                     (
-                        format!("{key}.{crate_name}.mean"),
-                        (statistical::mean(&durations) * 1000.0) as u64,
+                        key.sibling("mean").to_string(),
+                        Value::new(statistical::mean(&durations) * 1000.0, Unit::Milliseconds),
                     ),
                     (
-                        format!("{key}.{crate_name}.median"),
-                        (statistical::median(&durations) * 1000.0) as u64,
+                        key.sibling("median").to_string(),
+                        Value::new(statistical::median(&durations) * 1000.0, Unit::Milliseconds),
                     ),
                     (
-                        format!("{key}.{crate_name}.min"),
-                        (durations.iter().map(|d| (d * 1000.0) as u64).min().unwrap()),
+                        key.sibling("min").to_string(),
+                        Value::new(
+                            durations.iter().copied().fold(f64::INFINITY, f64::min) * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                     (
-                        format!("{key}.{crate_name}.max"),
-                        (durations.iter().map(|d| (d * 1000.0) as u64).max().unwrap()),
+                        key.sibling("max").to_string(),
+                        Value::new(
+                            durations.iter().copied().fold(f64::NEG_INFINITY, f64::max) * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                     (
-                        format!("{key}.{crate_name}.std_dev"),
-                        (statistical::standard_deviation(&durations, None) * 1000.0) as u64,
+                        key.sibling("std_dev").to_string(),
+                        Value::new(
+                            statistical::standard_deviation(&durations, None) * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                     (
-                        format!("{key}.{crate_name}.rmeta-mean"),
-                        (statistical::mean(&rmeta_times) * 1000.0) as u64,
+                        key.sibling("rmeta-mean").to_string(),
+                        Value::new(statistical::mean(&rmeta_times) * 1000.0, Unit::Milliseconds),
                     ),
                     (
-                        format!("{key}.{crate_name}.rmeta-median"),
-                        (statistical::median(&rmeta_times) * 1000.0) as u64,
+                        key.sibling("rmeta-median").to_string(),
+                        Value::new(
+                            statistical::median(&rmeta_times) * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                     (
-                        format!("{key}.{crate_name}.rmeta-min"),
-                        (rmeta_times
-                            .iter()
-                            .map(|d| (d * 1000.0) as u64)
-                            .min()
-                            .unwrap()),
+                        key.sibling("rmeta-min").to_string(),
+                        Value::new(
+                            rmeta_times.iter().copied().fold(f64::INFINITY, f64::min) * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                     (
-                        format!("{key}.{crate_name}.rmeta-max"),
-                        (rmeta_times
-                            .iter()
-                            .map(|d| (d * 1000.0) as u64)
-                            .max()
-                            .unwrap()),
+                        key.sibling("rmeta-max").to_string(),
+                        Value::new(
+                            rmeta_times
+                                .iter()
+                                .copied()
+                                .fold(f64::NEG_INFINITY, f64::max)
+                                * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                     (
-                        format!("{key}.{crate_name}.rmeta-std_dev"),
-                        (statistical::standard_deviation(&rmeta_times, None) * 1000.0) as u64,
+                        key.sibling("rmeta-std_dev").to_string(),
+                        Value::new(
+                            statistical::standard_deviation(&rmeta_times, None) * 1000.0,
+                            Unit::Milliseconds,
+                        ),
                     ),
                 ]
             })
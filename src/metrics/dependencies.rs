@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use xshell::{Shell, cmd};
+
+use crate::{Metrics, metric_key::MetricKey, stats::Value};
+
+#[derive(Debug)]
+pub struct Dependencies;
+
+impl Metrics for Dependencies {
+    fn prepare(&self) -> bool {
+        let sh = Shell::new().unwrap();
+        let Ok(out) = cmd!(sh, "cargo metadata --format-version 1").read() else {
+            return false;
+        };
+        let Ok(metadata) = serde_json::from_str::<CargoMetadata>(&out) else {
+            return false;
+        };
+
+        let file = File::create("metadata.json").unwrap();
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &metadata).unwrap();
+        true
+    }
+
+    fn artifacts(&self) -> HashMap<String, PathBuf> {
+        HashMap::from([(
+            "dependencies.stats".to_string(),
+            Path::new("metadata.json").to_path_buf(),
+        )])
+    }
+
+    fn collect(&self) -> HashMap<String, Value> {
+        let metadata: CargoMetadata =
+            serde_json::from_reader(std::fs::File::open("metadata.json").unwrap()).unwrap();
+
+        // Only keep packages that are actually part of the resolved graph
+        let resolved: HashSet<&str> = metadata
+            .resolve
+            .nodes
+            .iter()
+            .map(|node| node.id.as_str())
+            .collect();
+        let packages = metadata
+            .packages
+            .iter()
+            .filter(|package| resolved.contains(package.id.as_str()))
+            .collect::<Vec<_>>();
+
+        let third_party = packages.iter().filter(|p| p.source.is_some()).count();
+
+        let mut versions: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for package in &packages {
+            versions
+                .entry(package.name.as_str())
+                .or_default()
+                .insert(package.version.as_str());
+        }
+        let duplicates = versions.values().filter(|v| v.len() > 1).count();
+        let duplicated_versions = versions
+            .values()
+            .filter(|v| v.len() > 1)
+            .map(|v| v.len())
+            .sum::<usize>();
+
+        let proc_macros = packages
+            .iter()
+            .filter(|p| {
+                p.targets
+                    .iter()
+                    .any(|t| t.kind.iter().any(|k| k == "proc-macro"))
+            })
+            .count();
+        let build_scripts = packages
+            .iter()
+            .filter(|p| {
+                p.targets
+                    .iter()
+                    .any(|t| t.kind.iter().any(|k| k == "custom-build"))
+            })
+            .count();
+
+        let mut results = HashMap::from([
+            (
+                "dependencies.total.count".to_string(),
+                Value::count(packages.len() as u64),
+            ),
+            (
+                "dependencies.third-party.count".to_string(),
+                Value::count(third_party as u64),
+            ),
+            (
+                "dependencies.duplicates.count".to_string(),
+                Value::count(duplicates as u64),
+            ),
+            (
+                "dependencies.duplicates.versions".to_string(),
+                Value::count(duplicated_versions as u64),
+            ),
+            (
+                "dependencies.proc-macros.count".to_string(),
+                Value::count(proc_macros as u64),
+            ),
+            (
+                "dependencies.build-scripts.count".to_string(),
+                Value::count(build_scripts as u64),
+            ),
+        ]);
+
+        let graph: HashMap<&str, Vec<&str>> = metadata
+            .resolve
+            .nodes
+            .iter()
+            .map(|node| {
+                (
+                    node.id.as_str(),
+                    node.deps
+                        .iter()
+                        .filter(|dep| dep.is_compiled_by_users())
+                        .map(|dep| dep.pkg.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        for package in packages
+            .iter()
+            .filter(|p| p.source.is_none() && p.name.starts_with("bevy_"))
+        {
+            let mut seen = HashSet::new();
+            let mut to_visit = vec![package.id.as_str()];
+            while let Some(id) = to_visit.pop() {
+                for dep in graph.get(id).into_iter().flatten() {
+                    if seen.insert(*dep) {
+                        to_visit.push(dep);
+                    }
+                }
+            }
+            results.insert(
+                MetricKey::new("dependencies", "count")
+                    .with_subject(package.name.as_str())
+                    .to_string(),
+                Value::count(seen.len() as u64),
+            );
+        }
+
+        results
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CargoMetadata {
+    packages: Vec<Package>,
+    resolve: Resolve,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+    source: Option<String>,
+    targets: Vec<Target>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Target {
+    kind: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Resolve {
+    nodes: Vec<Node>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Node {
+    id: String,
+    deps: Vec<NodeDep>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct NodeDep {
+    pkg: String,
+    /// Missing with cargo versions older than 1.41
+    #[serde(default)]
+    dep_kinds: Vec<DepKind>,
+}
+
+impl NodeDep {
+    /// Normal and build dependencies, as opposed to dev-dependencies that are only built for
+    /// the tests, examples and benchmarks of the crate itself.
+    fn is_compiled_by_users(&self) -> bool {
+        self.dep_kinds.is_empty()
+            || self
+                .dep_kinds
+                .iter()
+                .any(|dep_kind| dep_kind.kind.as_deref() != Some("dev"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DepKind {
+    /// `None` for normal dependencies, `dev` or `build` otherwise
+    kind: Option<String>,
+}
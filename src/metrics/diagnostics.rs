@@ -0,0 +1,137 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use xshell::{Shell, cmd};
+
+use crate::{Metrics, metric_key::MetricKey, metrics::package_name, stats::Value};
+
+#[derive(Debug)]
+pub struct Diagnostics;
+
+impl Metrics for Diagnostics {
+    fn prepare(&self) -> bool {
+        let sh = Shell::new().unwrap();
+        cmd!(sh, "cargo clean").run().unwrap();
+        // Errors still produce diagnostics worth counting, so don't bail on failure
+        let out = cmd!(
+            sh,
+            "cargo clippy --workspace --all-targets --all-features --message-format json"
+        )
+        .ignore_status()
+        .read()
+        .unwrap();
+        let diagnostics = out
+            .lines()
+            .filter(|line| {
+                serde_json::from_str::<Message>(line)
+                    .is_ok_and(|message| message.reason == "compiler-message")
+            })
+            .map(|line| serde_json::from_str::<CompilerMessage>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        let file = File::create("diagnostics.json").unwrap();
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &diagnostics).unwrap();
+        true
+    }
+
+    fn artifacts(&self) -> HashMap<String, PathBuf> {
+        HashMap::from([(
+            "diagnostics.stats".to_string(),
+            Path::new("diagnostics.json").to_path_buf(),
+        )])
+    }
+
+    fn collect(&self) -> HashMap<String, Value> {
+        let diagnostics: Vec<CompilerMessage> =
+            serde_json::from_reader(std::fs::File::open("diagnostics.json").unwrap()).unwrap();
+
+        let mut results: HashMap<String, u64> = HashMap::new();
+        let mut seen = HashSet::new();
+        for diagnostic in diagnostics {
+            // Summaries like "5 warnings emitted" have no code and no span
+            let Some(span) = diagnostic.message.spans.first() else {
+                continue;
+            };
+            let level = diagnostic.message.level.as_str();
+            let code = diagnostic
+                .message
+                .code
+                .as_ref()
+                .map(|code| reserved(&code.code, &["total"]));
+            let lint = code.clone().unwrap_or_else(|| level.to_string());
+            let crate_name = reserved(package_name(&diagnostic.package_id), &["all", "level"]);
+
+            // The same diagnostic is reported once per target (lib, tests, ...) of a package
+            if !seen.insert((
+                crate_name.clone(),
+                lint.clone(),
+                span.file_name.clone(),
+                span.line_start,
+            )) {
+                continue;
+            }
+
+            let mut keys = vec![
+                MetricKey::new("diagnostics", lint.as_str()).with_subject(crate_name.as_str()),
+                MetricKey::new("diagnostics", "total").with_subject(crate_name.as_str()),
+                MetricKey::new("diagnostics", level).with_subject("level"),
+            ];
+            if let Some(code) = code {
+                keys.push(MetricKey::new("diagnostics", code).with_subject("all"));
+            }
+            for key in keys {
+                *results.entry(key.to_string()).or_default() += 1;
+            }
+        }
+        results
+            .into_iter()
+            .map(|(key, count)| (key, Value::count(count)))
+            .collect()
+    }
+}
+
+/// Names used by the aggregated metrics get a leading colon, which can't be in a package name
+/// or start a lint name, so that a crate called `all` doesn't mix with the totals over all
+/// crates.
+fn reserved(name: &str, reserved: &[&str]) -> String {
+    if reserved.contains(&name) {
+        format!(":{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Message {
+    reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CompilerMessage {
+    package_id: String,
+    message: Diagnostic,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Diagnostic {
+    level: String,
+    code: Option<DiagnosticCode>,
+    spans: Vec<Span>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DiagnosticCode {
+    code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Span {
+    file_name: String,
+    line_start: u32,
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use xshell::{Shell, cmd};
+
+use crate::{
+    Metrics,
+    metric_key::MetricKey,
+    metrics::compile_time::Hyperfine,
+    stats::{Unit, Value},
+};
+
+#[derive(Debug)]
+pub struct DocBuild;
+
+impl Metrics for DocBuild {
+    fn prepare(&self) -> bool {
+        let command = "cargo doc --workspace --no-deps";
+        let sh = Shell::new().unwrap();
+        if cmd!(
+            sh,
+            "hyperfine --export-json doc-clean.json --prepare 'cargo clean; sleep 2' {command}"
+        )
+        .run()
+        .is_err()
+        {
+            return false;
+        }
+        cmd!(
+            sh,
+            "hyperfine --export-json doc-incremental.json --warmup 1 --prepare 'touch src/lib.rs; sleep 2' {command}"
+        )
+        .run()
+        .is_ok()
+    }
+
+    fn artifacts(&self) -> HashMap<String, PathBuf> {
+        HashMap::from([
+            (
+                "doc-build-time.clean.stats".to_string(),
+                Path::new("doc-clean.json").to_path_buf(),
+            ),
+            (
+                "doc-build-time.incremental.stats".to_string(),
+                Path::new("doc-incremental.json").to_path_buf(),
+            ),
+        ])
+    }
+
+    fn collect(&self) -> HashMap<String, Value> {
+        let key = MetricKey::new("doc-build-time", "mean").with_variant(MetricKey::platform(None));
+
+        let mut results = HashMap::new();
+        for kind in ["clean", "incremental"] {
+            let hyperfine: Hyperfine =
+                serde_json::from_reader(std::fs::File::open(format!("doc-{kind}.json")).unwrap())
+                    .unwrap();
+            let timings = &hyperfine.results[0];
+            let key = key.clone().with_subject(kind);
+            results.extend([
+                (
+                    key.sibling("mean").to_string(),
+                    Value::new(timings.mean * 1000.0, Unit::Milliseconds),
+                ),
+                (
+                    key.sibling("stddev").to_string(),
+                    Value::new(
+                        timings.stddev.unwrap_or_default() * 1000.0,
+                        Unit::Milliseconds,
+                    ),
+                ),
+                (
+                    key.sibling("median").to_string(),
+                    Value::new(timings.median * 1000.0, Unit::Milliseconds),
+                ),
+                (
+                    key.sibling("min").to_string(),
+                    Value::new(timings.min * 1000.0, Unit::Milliseconds),
+                ),
+                (
+                    key.sibling("max").to_string(),
+                    Value::new(timings.max * 1000.0, Unit::Milliseconds),
+                ),
+            ]);
+        }
+
+        let doc_dir = Path::new("target/doc");
+        results.insert(
+            MetricKey::new("doc", "size").to_string(),
+            Value::bytes(dir_size(doc_dir, &|_| true)),
+        );
+        // Older rustdoc writes a single `search-index.js`, newer ones a `search.index` folder
+        results.insert(
+            MetricKey::new("doc", "size")
+                .with_subject("search-index")
+                .to_string(),
+            Value::bytes(dir_size(doc_dir, &|path| {
+                path.components().any(|c| {
+                    let c = c.as_os_str().to_string_lossy();
+                    c.starts_with("search-index") || c == "search.index"
+                })
+            })),
+        );
+        results
+    }
+}
+
+fn dir_size(root: &Path, filter: &dyn Fn(&Path) -> bool) -> u64 {
+    let mut size = 0;
+    if let Ok(entries) = std::fs::read_dir(root) {
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                size += dir_size(&entry.path(), filter);
+            } else if file_type.is_file() && filter(&entry.path()) {
+                size += entry.metadata().map(|m| m.len()).unwrap_or_default();
+            }
+        }
+    }
+    size
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use xshell::{Shell, cmd};
+
+use crate::{
+    Metrics,
+    metric_key::MetricKey,
+    stats::{Unit, Value},
+};
+
+#[derive(Debug)]
+pub struct ExampleBuilds;
+
+impl Metrics for ExampleBuilds {
+    fn prepare(&self) -> bool {
+        let Ok(manifest) = std::fs::read_to_string("Cargo.toml") else {
+            return false;
+        };
+        let Ok(manifest) = toml::from_str::<Manifest>(&manifest) else {
+            return false;
+        };
+
+        let sh = Shell::new().unwrap();
+        // Build the dependencies once so that each example only measures its own cost
+        if cmd!(sh, "cargo build --lib").run().is_err() {
+            return false;
+        }
+
+        // Switching features rebuilds Bevy, so examples sharing their features are built
+        // together, after building the library with those features
+        let mut examples = manifest.example;
+        for example in &mut examples {
+            example.required_features.sort();
+        }
+        examples
+            .sort_by(|a, b| (&a.required_features, &a.name).cmp(&(&b.required_features, &b.name)));
+        let mut warmed_up: Option<Vec<String>> = None;
+        let builds = examples
+            .into_iter()
+            .map(|example| {
+                let name = example.name;
+                let features = &example
+                    .required_features
+                    .iter()
+                    .flat_map(|f| ["--features".to_string(), f.clone()])
+                    .collect::<Vec<_>>();
+                if warmed_up.as_ref() != Some(&example.required_features) {
+                    // A failure here shows up as failed builds of the examples
+                    let _ = cmd!(sh, "cargo build {features...} --lib").run();
+                    warmed_up = Some(example.required_features);
+                }
+                let start = Instant::now();
+                let success = cmd!(sh, "cargo build {features...} --example {name}")
+                    .run()
+                    .is_ok();
+                ExampleBuild {
+                    name,
+                    success,
+                    duration: start.elapsed().as_secs_f64(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let file = File::create("examples.json").unwrap();
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &builds).unwrap();
+        true
+    }
+
+    fn artifacts(&self) -> HashMap<String, PathBuf> {
+        HashMap::from([(
+            "examples.stats".to_string(),
+            Path::new("examples.json").to_path_buf(),
+        )])
+    }
+
+    fn collect(&self) -> HashMap<String, Value> {
+        let builds: Vec<ExampleBuild> =
+            serde_json::from_reader(std::fs::File::open("examples.json").unwrap()).unwrap();
+
+        let mut results = HashMap::from([
+            (
+                "examples.total.succeeded".to_string(),
+                Value::count(builds.iter().filter(|b| b.success).count() as u64),
+            ),
+            (
+                "examples.total.failed".to_string(),
+                Value::count(builds.iter().filter(|b| !b.success).count() as u64),
+            ),
+        ]);
+        for build in &builds {
+            let key = MetricKey::new("examples", "success").with_subject(build.name.as_str());
+            results.insert(key.to_string(), Value::count(build.success as u64));
+            if build.success {
+                results.insert(
+                    key.sibling("build_time").to_string(),
+                    Value::new(build.duration * 1000.0, Unit::Milliseconds),
+                );
+            }
+        }
+        results
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Manifest {
+    #[serde(default)]
+    example: Vec<Example>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Example {
+    name: String,
+    #[serde(rename = "required-features", default)]
+    required_features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ExampleBuild {
+    name: String,
+    success: bool,
+    duration: f64,
+}
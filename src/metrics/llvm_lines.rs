@@ -6,7 +6,7 @@ use std::{
 use regex::Regex;
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{Metrics, metric_key::MetricKey, stats::Value};
 
 #[derive(Debug)]
 pub struct LlvmLines;
@@ -21,7 +21,7 @@ impl Metrics for LlvmLines {
         HashMap::from([("llvm-lines".to_string(), Path::new("done").to_path_buf())])
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
+    fn collect(&self) -> HashMap<String, Value> {
         let sh = Shell::new().unwrap();
         let out = cmd!(sh, "cargo llvm-lines --release --example breakout")
             .read()
@@ -33,14 +33,16 @@ impl Metrics for LlvmLines {
         out.lines()
             .filter_map(|line| re.captures(line))
             .flat_map(|captured| {
+                let key = MetricKey::new("llvm-lines", "lines")
+                    .with_subject(captured.get(3).unwrap().as_str());
                 [
                     (
-                        format!("llvm-lines.{}.lines", captured.get(3).unwrap().as_str()),
-                        captured.get(1).unwrap().as_str().parse::<u64>().unwrap(),
+                        key.to_string(),
+                        Value::count(captured.get(1).unwrap().as_str().parse::<u64>().unwrap()),
                     ),
                     (
-                        format!("llvm-lines.{}.copies", captured.get(3).unwrap().as_str()),
-                        captured.get(2).unwrap().as_str().parse::<u64>().unwrap(),
+                        key.sibling("copies").to_string(),
+                        Value::count(captured.get(2).unwrap().as_str().parse::<u64>().unwrap()),
                     ),
                 ]
             })
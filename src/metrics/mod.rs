@@ -1,8 +1,90 @@
 pub mod benchmarks;
 pub mod binary_size;
+pub mod callgrind;
 pub mod compile_time;
 pub mod crate_compile_time;
+pub mod dependencies;
+pub mod diagnostics;
+pub mod doc_build;
+pub mod example_builds;
 pub mod large_scenes;
 pub mod llvm_lines;
 pub mod stress_tests;
+pub mod test_suite;
 pub mod wasm_binary_size;
+
+use std::collections::HashMap;
+
+use crate::{
+    metric_key::MetricKey,
+    stats::{Unit, Value},
+};
+
+/// Name of the package in a cargo package id, either `bevy_ecs 0.15.0 (path+file:///...)` or
+/// a package id spec like `path+file:///.../bevy_ecs#0.15.0` or `registry+https://...#serde@1.0.0`.
+pub(crate) fn package_name(package_id: &str) -> &str {
+    let Some((url, fragment)) = package_id.split_once('#') else {
+        return package_id.split(' ').next().unwrap_or_default();
+    };
+    match fragment.split_once('@') {
+        Some((name, _version)) => name,
+        // The name is left out when it's the same as the folder
+        None => url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default(),
+    }
+}
+
+/// Inserts the usual statistics of a series of samples under `key`.
+pub(crate) fn insert_series(
+    results: &mut HashMap<String, Value>,
+    key: &MetricKey,
+    values: &[f32],
+    unit: Unit,
+) {
+    let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    results.extend([
+        (
+            key.sibling("mean").to_string(),
+            Value::new(statistical::mean(values) as f64, unit),
+        ),
+        (
+            key.sibling("median").to_string(),
+            Value::new(statistical::median(values) as f64, unit),
+        ),
+        (key.sibling("min").to_string(), Value::new(min as f64, unit)),
+        (key.sibling("max").to_string(), Value::new(max as f64, unit)),
+        (
+            key.sibling("std_dev").to_string(),
+            Value::new(statistical::standard_deviation(values, None) as f64, unit),
+        ),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_names() {
+        assert_eq!(
+            package_name("bevy_ecs 0.15.0 (path+file:///bevy/crates/bevy_ecs)"),
+            "bevy_ecs"
+        );
+        assert_eq!(
+            package_name("path+file:///bevy/crates/bevy_ecs#0.15.0"),
+            "bevy_ecs"
+        );
+        assert_eq!(
+            package_name("path+file:///bevy/tools/ci#ci-tool@0.1.0"),
+            "ci-tool"
+        );
+        assert_eq!(
+            package_name("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.219"),
+            "serde"
+        );
+    }
+}
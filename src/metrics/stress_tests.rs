@@ -9,7 +9,13 @@ use std::{
 use crossbeam::channel::Receiver;
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{
+    Metrics,
+    metric_key::MetricKey,
+    stats::{Unit, Value},
+};
+
+use super::insert_series;
 
 #[derive(Debug)]
 pub struct StressTest {
@@ -37,6 +43,19 @@ impl StressTest {
         self.features = features.into_iter().map(|f| f.to_string()).collect();
         self
     }
+
+    pub fn params(&self) -> String {
+        self.parameters
+            .iter()
+            .map(|(p, v)| {
+                if let Some(v) = v {
+                    format!("{}-{}", p, v)
+                } else {
+                    p.clone()
+                }
+            })
+            .fold("params".to_string(), |acc, s| format!("{}-{}", acc, s))
+    }
 }
 
 impl Metrics for StressTest {
@@ -60,38 +79,18 @@ impl Metrics for StressTest {
     fn artifacts(&self) -> HashMap<String, PathBuf> {
         std::fs::File::create("done").unwrap();
         HashMap::from([(
-            format!(
-                "stress-test-fps.{}.{}",
-                self.stress_test,
-                self.parameters
-                    .iter()
-                    .map(|(p, v)| if let Some(v) = v {
-                        format!("{}-{}", p, v)
-                    } else {
-                        p.clone()
-                    })
-                    .fold("params".to_string(), |acc, s| format!("{}-{}", acc, s))
-            ),
+            format!("stress-test-fps.{}.{}", self.stress_test, self.params()),
             Path::new("done").to_path_buf(),
         )])
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
+    fn collect(&self) -> HashMap<String, Value> {
         let cpu = cpu_usage();
         let gpu = gpu_usage();
 
-        let key = format!(
-            "stress-test-fps.{}.{}",
-            self.stress_test,
-            self.parameters
-                .iter()
-                .map(|(p, v)| if let Some(v) = v {
-                    format!("{}-{}", p, v)
-                } else {
-                    p.clone()
-                })
-                .fold("params".to_string(), |acc, s| format!("{}-{}", acc, s))
-        );
+        let key = MetricKey::new("stress-test-fps", "mean")
+            .with_subject(self.stress_test.clone())
+            .with_variant(self.params());
         let config = "twitcher_config.ron";
         let mut config_file = std::fs::File::create(config).unwrap();
         config_file
@@ -176,103 +175,40 @@ impl Metrics for StressTest {
             .collect::<Vec<_>>();
 
         if !fpss.is_empty() {
-            results.insert(
-                format!("{key}.mean"),
-                (statistical::mean(&fpss) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.median"),
-                (statistical::median(&fpss) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.min"),
-                fpss.iter().map(|d| (d * 1000.0) as u64).min().unwrap(),
-            );
-            results.insert(
-                format!("{key}.max"),
-                fpss.iter().map(|d| (d * 1000.0) as u64).max().unwrap(),
-            );
-            results.insert(
-                format!("{key}.std_dev"),
-                (statistical::standard_deviation(&fpss, None) * 1000.0) as u64,
-            );
+            insert_series(&mut results, &key, &fpss, Unit::Fps);
         }
         if cpu_usage.len() > 3 {
-            results.insert(
-                format!("{key}.cpu_usage.mean"),
-                (statistical::mean(&cpu_usage) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.cpu_usage.median"),
-                (statistical::median(&cpu_usage) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.cpu_usage.min"),
-                cpu_usage.iter().map(|d| (d * 1000.0) as u64).min().unwrap(),
-            );
-            results.insert(
-                format!("{key}.cpu_usage.max"),
-                cpu_usage.iter().map(|d| (d * 1000.0) as u64).max().unwrap(),
-            );
-            results.insert(
-                format!("{key}.cpu_usage.std_dev"),
-                (statistical::standard_deviation(&cpu_usage, None) * 1000.0) as u64,
+            insert_series(
+                &mut results,
+                &key.clone().with_dimension("cpu_usage"),
+                &cpu_usage,
+                Unit::Percent,
             );
         }
         if gpu_usage.len() > 3 {
-            results.insert(
-                format!("{key}.gpu_usage.mean"),
-                (statistical::mean(&gpu_usage) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.gpu_usage.median"),
-                (statistical::median(&gpu_usage) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.gpu_usage.min"),
-                gpu_usage.iter().map(|d| (d * 1000.0) as u64).min().unwrap(),
-            );
-            results.insert(
-                format!("{key}.gpu_usage.max"),
-                gpu_usage.iter().map(|d| (d * 1000.0) as u64).max().unwrap(),
-            );
-            results.insert(
-                format!("{key}.gpu_usage.std_dev"),
-                (statistical::standard_deviation(&gpu_usage, None) * 1000.0) as u64,
+            insert_series(
+                &mut results,
+                &key.clone().with_dimension("gpu_usage"),
+                &gpu_usage,
+                Unit::Percent,
             );
         }
         if gpu_memory.len() > 3 {
-            results.insert(
-                format!("{key}.gpu_memory.mean"),
-                (statistical::mean(&gpu_memory) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.gpu_memory.median"),
-                (statistical::median(&gpu_memory) * 1000.0) as u64,
-            );
-            results.insert(
-                format!("{key}.gpu_memory.min"),
-                gpu_memory
-                    .iter()
-                    .map(|d| (d * 1000.0) as u64)
-                    .min()
-                    .unwrap(),
-            );
-            results.insert(
-                format!("{key}.gpu_memory.max"),
-                gpu_memory
-                    .iter()
-                    .map(|d| (d * 1000.0) as u64)
-                    .max()
-                    .unwrap(),
-            );
-            results.insert(
-                format!("{key}.gpu_memory.std_dev"),
-                (statistical::standard_deviation(&gpu_memory, None) * 1000.0) as u64,
+            insert_series(
+                &mut results,
+                &key.clone().with_dimension("gpu_memory"),
+                &gpu_memory,
+                Unit::Percent,
             );
         }
-        results.insert(format!("{key}.duration"), elapsed.as_millis() as u64);
-        results.insert(format!("{key}.frames"), self.nb_frames as u64);
+        results.insert(
+            key.sibling("duration").to_string(),
+            Value::new(elapsed.as_secs_f64() * 1000.0, Unit::Milliseconds),
+        );
+        results.insert(
+            key.sibling("frames").to_string(),
+            Value::count(self.nb_frames as u64),
+        );
 
         if let Some(last_modified_file) = std::fs::read_dir(".")
             .expect("Couldn't access local directory")
@@ -297,33 +233,11 @@ impl Metrics for StressTest {
                 .collect::<Vec<_>>();
 
             if !frame_times.len() > 3 {
-                results.insert(
-                    format!("{key}.frame_time.mean"),
-                    (statistical::mean(&frame_times) * 1000.0) as u64,
-                );
-                results.insert(
-                    format!("{key}.frame_time.median"),
-                    (statistical::median(&frame_times) * 1000.0) as u64,
-                );
-                results.insert(
-                    format!("{key}.frame_time.min"),
-                    frame_times
-                        .iter()
-                        .map(|d| (d * 1000.0) as u64)
-                        .min()
-                        .unwrap(),
-                );
-                results.insert(
-                    format!("{key}.frame_time.max"),
-                    frame_times
-                        .iter()
-                        .map(|d| (d * 1000.0) as u64)
-                        .max()
-                        .unwrap(),
-                );
-                results.insert(
-                    format!("{key}.frame_time.std_dev"),
-                    (statistical::standard_deviation(&frame_times, None) * 1000.0) as u64,
+                insert_series(
+                    &mut results,
+                    &key.clone().with_dimension("frame_time"),
+                    &frame_times,
+                    Unit::Milliseconds,
                 );
             }
         }
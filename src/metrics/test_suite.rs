@@ -0,0 +1,265 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use xshell::{Shell, cmd};
+
+use crate::{
+    Metrics,
+    metric_key::MetricKey,
+    metrics::package_name,
+    stats::{Unit, Value},
+};
+
+const NB_SLOWEST: usize = 20;
+
+#[derive(Debug)]
+pub struct TestSuite;
+
+impl Metrics for TestSuite {
+    fn prepare(&self) -> bool {
+        let sh = Shell::new().unwrap();
+        let build = cmd!(
+            sh,
+            "cargo +nightly test --workspace --no-run --message-format json"
+        )
+        .ignore_status()
+        .output()
+        .unwrap();
+        // Test binaries of different packages can have the same name, like `tests/ui.rs`
+        let targets = String::from_utf8_lossy(&build.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Artifact>(line).ok())
+            .filter_map(|artifact| {
+                let executable = Path::new(artifact.executable.as_ref()?);
+                let stem = executable.file_stem()?.to_string_lossy().to_string();
+                let package = package_name(&artifact.package_id);
+                let subject = if artifact.target.kind.iter().any(|kind| kind == "lib") {
+                    package.to_string()
+                } else {
+                    format!("{package}/{}", artifact.target.name)
+                };
+                Some((stem, subject))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut run = TestRun {
+            status: TestStatus::BuildFailed,
+            duration: 0.0,
+            suites: vec![],
+            slowest: vec![],
+        };
+
+        if build.status.success() {
+            // libtest writes JSON to stdout while cargo announces each binary on stderr, merge
+            // them to know which binary each suite belongs to
+            let command = "cargo +nightly test --workspace --no-fail-fast -- -Z unstable-options --format json --report-time 2>&1";
+            let start = Instant::now();
+            let output = cmd!(sh, "sh -c {command}")
+                .ignore_status()
+                .output()
+                .unwrap();
+            run.duration = start.elapsed().as_secs_f64();
+            run.suites = parse_suites(&String::from_utf8_lossy(&output.stdout), &targets);
+            // Test names change too often to make a metric of each, they're only kept here
+            let mut tests = run
+                .suites
+                .iter()
+                .flat_map(|suite| {
+                    suite.tests.iter().map(move |(name, duration)| {
+                        (format!("{}::{name}", suite.binary), *duration)
+                    })
+                })
+                .collect::<Vec<_>>();
+            tests.sort_by(|a, b| b.1.total_cmp(&a.1));
+            tests.truncate(NB_SLOWEST);
+            run.slowest = tests;
+            run.status = if output.status.success() {
+                TestStatus::Passed
+            } else {
+                TestStatus::Failed
+            };
+        }
+
+        let file = File::create("tests.json").unwrap();
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &run).unwrap();
+        true
+    }
+
+    fn artifacts(&self) -> HashMap<String, PathBuf> {
+        HashMap::from([(
+            "tests.stats".to_string(),
+            Path::new("tests.json").to_path_buf(),
+        )])
+    }
+
+    fn collect(&self) -> HashMap<String, Value> {
+        let run: TestRun =
+            serde_json::from_reader(std::fs::File::open("tests.json").unwrap()).unwrap();
+
+        let mut results = HashMap::from([
+            (
+                "tests.total.status".to_string(),
+                Value::count(run.status as u64),
+            ),
+            (
+                "tests.total.duration".to_string(),
+                Value::new(run.duration * 1000.0, Unit::Milliseconds),
+            ),
+            (
+                "tests.total.passed".to_string(),
+                Value::count(run.suites.iter().map(|s| s.passed).sum()),
+            ),
+            (
+                "tests.total.failed".to_string(),
+                Value::count(run.suites.iter().map(|s| s.failed).sum()),
+            ),
+            (
+                "tests.total.ignored".to_string(),
+                Value::count(run.suites.iter().map(|s| s.ignored).sum()),
+            ),
+        ]);
+
+        for suite in run
+            .suites
+            .iter()
+            .filter(|s| s.passed + s.failed + s.ignored > 0)
+        {
+            let key = MetricKey::new("tests", "duration").with_subject(suite.binary.as_str());
+            results.insert(
+                key.to_string(),
+                Value::new(suite.duration * 1000.0, Unit::Milliseconds),
+            );
+            results.insert(
+                key.sibling("passed").to_string(),
+                Value::count(suite.passed),
+            );
+            results.insert(
+                key.sibling("failed").to_string(),
+                Value::count(suite.failed),
+            );
+            results.insert(
+                key.sibling("ignored").to_string(),
+                Value::count(suite.ignored),
+            );
+        }
+
+        results
+    }
+}
+
+/// Splits the merged cargo and libtest output into one entry per test binary, named after
+/// their package with `targets`, from the file names of the binaries.
+fn parse_suites(output: &str, targets: &HashMap<String, String>) -> Vec<Suite> {
+    let mut suites = vec![];
+    let mut binary = String::new();
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(running) = trimmed.strip_prefix("Running ") {
+            // Running unittests src/lib.rs (target/debug/deps/bevy_ecs-0123456789abcdef)
+            let stem = running
+                .rsplit(['/', '\\'])
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches(')')
+                .trim_end_matches(".exe");
+            binary = match targets.get(stem) {
+                Some(subject) => subject.clone(),
+                None => stem
+                    .rsplit_once('-')
+                    .map(|(name, _hash)| name.to_string())
+                    .unwrap_or_default(),
+            };
+            continue;
+        }
+        if let Some(crate_name) = trimmed.strip_prefix("Doc-tests ") {
+            binary = format!("{crate_name}-doc");
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<TestEvent>(line) else {
+            continue;
+        };
+        match (event.kind.as_str(), event.event.as_str()) {
+            ("suite", "started") => suites.push(Suite {
+                binary: binary.clone(),
+                ..Default::default()
+            }),
+            ("suite", _) => {
+                if let Some(suite) = suites.last_mut() {
+                    suite.passed = event.passed.unwrap_or_default();
+                    suite.failed = event.failed.unwrap_or_default();
+                    suite.ignored = event.ignored.unwrap_or_default();
+                    suite.duration = event.exec_time.unwrap_or_default();
+                }
+            }
+            ("test", "ok" | "failed") => {
+                if let (Some(suite), Some(name), Some(exec_time)) =
+                    (suites.last_mut(), event.name, event.exec_time)
+                {
+                    suite.tests.push((name, exec_time));
+                }
+            }
+            _ => {}
+        }
+    }
+    suites
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+enum TestStatus {
+    Passed = 0,
+    Failed = 1,
+    BuildFailed = 2,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TestRun {
+    status: TestStatus,
+    duration: f64,
+    suites: Vec<Suite>,
+    /// Full names of the slowest tests, with their duration in seconds
+    #[serde(default)]
+    slowest: Vec<(String, f64)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Suite {
+    /// Package of the test binary, followed by the name of the target if it's not the library
+    binary: String,
+    duration: f64,
+    passed: u64,
+    failed: u64,
+    ignored: u64,
+    tests: Vec<(String, f64)>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Artifact {
+    package_id: String,
+    target: ArtifactTarget,
+    executable: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ArtifactTarget {
+    name: String,
+    kind: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+    passed: Option<u64>,
+    failed: Option<u64>,
+    ignored: Option<u64>,
+    exec_time: Option<f64>,
+}
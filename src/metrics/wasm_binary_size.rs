@@ -5,7 +5,7 @@ use std::{
 
 use xshell::{Shell, cmd};
 
-use crate::Metrics;
+use crate::{Metrics, metric_key::MetricKey, stats::Value};
 
 #[derive(Debug)]
 pub struct WasmBinarySize {
@@ -44,16 +44,17 @@ impl Metrics for WasmBinarySize {
         )])
     }
 
-    fn collect(&self) -> HashMap<String, u64> {
+    fn collect(&self) -> HashMap<String, Value> {
         let wasm_file = Path::new("examples/wasm/target/wasm_example_bg.wasm");
         let size = wasm_file.metadata().unwrap().len();
         let optimized_wasm_file = Path::new("examples/wasm/target/wasm_example_bg.wasm.optimized");
         let optimized_size = optimized_wasm_file.metadata().unwrap().len();
+        let key = MetricKey::new("wasm32-unknown-unknown", "size");
         HashMap::from([
-            ("wasm32-unknown-unknown.size".to_string(), size),
+            (key.to_string(), Value::bytes(size)),
             (
-                "wasm32-unknown-unknown.optimized.size".to_string(),
-                optimized_size,
+                key.with_subject("optimized").to_string(),
+                Value::bytes(optimized_size),
             ),
         ])
     }
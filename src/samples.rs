@@ -0,0 +1,252 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+pub const SAMPLES_FOLDER: &str = "benchmarks.samples";
+pub const SAMPLES_FILE: &str = "benchmarks-samples.json.gz";
+/// Samples of partial runs are saved with this prefix, followed by when the run started in
+/// milliseconds and a hash of what was selected.
+pub const PARTIAL_SAMPLES_PREFIX: &str = "benchmarks-samples-partial";
+
+/// Raw measurements of a criterion benchmark, as reported by `cargo criterion`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BenchmarkSamples {
+    pub id: String,
+    pub unit: String,
+    pub iteration_count: Vec<f64>,
+    pub measured_values: Vec<f64>,
+}
+
+impl BenchmarkSamples {
+    /// Time per iteration of each sample, in nanoseconds, `None` if the unit is not a duration.
+    pub fn per_iteration(&self) -> Option<Vec<f64>> {
+        let scale = match self.unit.as_str() {
+            "ps" => 0.001,
+            "ns" => 1.0,
+            "us" | "µs" => 1_000.0,
+            "ms" => 1_000_000.0,
+            "s" => 1_000_000_000.0,
+            _ => return None,
+        };
+        Some(
+            self.iteration_count
+                .iter()
+                .zip(&self.measured_values)
+                .filter(|(iterations, _)| **iterations > 0.0)
+                .map(|(iterations, value)| value * scale / iterations)
+                .collect(),
+        )
+    }
+}
+
+pub fn write_samples(path: &Path, samples: &[BenchmarkSamples]) -> std::io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::best());
+    serde_json::to_writer(&mut encoder, samples)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+pub fn read_samples(path: &Path) -> std::io::Result<Vec<BenchmarkSamples>> {
+    let file = File::open(path)?;
+    let decoder = GzDecoder::new(BufReader::new(file));
+    Ok(serde_json::from_reader(decoder)?)
+}
+
+/// Reads the samples archived for a commit, with the ones from partial runs taking precedence,
+/// the most recent ones last.
+pub fn read_commit_samples(commit_dir: &Path) -> HashMap<String, BenchmarkSamples> {
+    let folder = commit_dir.join(SAMPLES_FOLDER);
+    let mut partial_files = std::fs::read_dir(&folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(PARTIAL_SAMPLES_PREFIX)
+        })
+        .map(|entry| {
+            // Modification times are reset by every checkout of the results, so the start of
+            // the run is in the name. Files from before it was are read first
+            let name = entry.file_name().to_string_lossy().to_string();
+            let started = name
+                .strip_prefix(PARTIAL_SAMPLES_PREFIX)
+                .and_then(|rest| rest.strip_prefix('-')?.split_once('-'))
+                .and_then(|(started, _)| started.parse::<u128>().ok())
+                .unwrap_or_default();
+            (started, entry.path())
+        })
+        .collect::<Vec<_>>();
+    partial_files.sort();
+    std::iter::once(folder.join(SAMPLES_FILE))
+        .chain(partial_files.into_iter().map(|(_, path)| path))
+        .flat_map(|path| read_samples(&path).unwrap_or_default())
+        .map(|samples| (samples.id.clone(), samples))
+        .collect()
+}
+
+/// Result of comparing two sets of samples with a Mann-Whitney U test.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Comparison {
+    pub base_median: f64,
+    pub other_median: f64,
+    /// Relative change of the median, `0.1` is 10% slower
+    pub change: f64,
+    /// Two-sided p-value, using the normal approximation with tie correction
+    pub p_value: f64,
+    /// Cliff's delta, from -1 (other always faster) to 1 (other always slower)
+    pub effect_size: f64,
+}
+
+pub fn compare(base: &[f64], other: &[f64]) -> Option<Comparison> {
+    if base.len() < 2 || other.len() < 2 {
+        return None;
+    }
+    let n1 = base.len() as f64;
+    let n2 = other.len() as f64;
+
+    let mut all = base
+        .iter()
+        .map(|v| (*v, true))
+        .chain(other.iter().map(|v| (*v, false)))
+        .collect::<Vec<_>>();
+    all.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Average ranks over ties, and keep track of them for the variance correction
+    let mut rank_sum_base = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < all.len() {
+        let mut j = i;
+        while j + 1 < all.len() && all[j + 1].0 == all[i].0 {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_correction += ties.powi(3) - ties;
+        rank_sum_base += all[i..=j].iter().filter(|v| v.1).count() as f64 * rank;
+        i = j + 1;
+    }
+
+    let u_base = rank_sum_base - n1 * (n1 + 1.0) / 2.0;
+    let u_other = n1 * n2 - u_base;
+    let n = n1 + n2;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)));
+    let p_value = if variance > 0.0 {
+        let z = (u_base.min(u_other) - n1 * n2 / 2.0).abs() / variance.sqrt();
+        erfc(z / std::f64::consts::SQRT_2).min(1.0)
+    } else {
+        1.0
+    };
+
+    let base_median = statistical::median(base);
+    let other_median = statistical::median(other);
+    Some(Comparison {
+        base_median,
+        other_median,
+        change: (other_median - base_median) / base_median,
+        p_value,
+        effect_size: (u_other - u_base) / (n1 * n2),
+    })
+}
+
+/// Complementary error function, with a maximum error of 1.2e-7 (Numerical Recipes).
+pub(crate) fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn partial_samples_by_run() {
+        let commit_dir = std::env::temp_dir().join(format!("samples-{}", std::process::id()));
+        let folder = commit_dir.join(SAMPLES_FOLDER);
+        std::fs::create_dir_all(&folder).unwrap();
+        let samples = |value: f64| {
+            vec![BenchmarkSamples {
+                id: "spawn/10".to_string(),
+                unit: "ns".to_string(),
+                iteration_count: vec![1.0],
+                measured_values: vec![value],
+            }]
+        };
+        // Written in the opposite order of the runs, so that modification times disagree
+        let name = |started: &str| format!("{PARTIAL_SAMPLES_PREFIX}-{started}-0123.json.gz");
+        write_samples(&folder.join(name("1700000002000")), &samples(3.0)).unwrap();
+        write_samples(&folder.join(name("1700000001000")), &samples(2.0)).unwrap();
+        write_samples(&folder.join(SAMPLES_FILE), &samples(1.0)).unwrap();
+
+        let read = read_commit_samples(&commit_dir);
+        std::fs::remove_dir_all(&commit_dir).unwrap();
+        assert_eq!(read["spawn/10"].measured_values, [3.0]);
+    }
+
+    #[test]
+    fn erfc_known_values() {
+        assert_close(erfc(0.0), 1.0, 1e-7);
+        assert_close(erfc(1.0), 0.1572992, 1e-6);
+        assert_close(erfc(-1.0), 1.8427008, 1e-6);
+        assert_close(erfc(5.0), 0.0, 1e-6);
+    }
+
+    #[test]
+    fn separated_samples() {
+        // U = 0, z = 12.5 / sqrt(25 * 11 / 12)
+        let comparison = compare(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]).unwrap();
+        assert_close(comparison.p_value, 0.0090234, 1e-5);
+        assert_close(comparison.effect_size, 1.0, 1e-12);
+        assert_close(comparison.base_median, 3.0, 1e-12);
+        assert_close(comparison.other_median, 8.0, 1e-12);
+        assert_close(comparison.change, 5.0 / 3.0, 1e-12);
+
+        let reversed = compare(&[6.0, 7.0, 8.0, 9.0, 10.0], &[1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+        assert_close(reversed.p_value, comparison.p_value, 1e-12);
+        assert_close(reversed.effect_size, -1.0, 1e-12);
+    }
+
+    #[test]
+    fn ties() {
+        // Ranks of the base are 1, 3, 3, 5.5 and 8.5, so U = 6 against 19 for the other, and
+        // the ties of 2, 3 and 5 reduce the variance to 25 / 12 * (11 - 36 / 90)
+        let comparison = compare(&[1.0, 2.0, 2.0, 3.0, 5.0], &[2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        assert_close(comparison.p_value, 0.1666074, 1e-5);
+        assert_close(comparison.effect_size, 0.52, 1e-12);
+    }
+
+    #[test]
+    fn identical_samples() {
+        let comparison = compare(&[2.0, 2.0, 2.0], &[2.0, 2.0, 2.0]).unwrap();
+        assert_eq!(comparison.p_value, 1.0);
+        assert_eq!(comparison.effect_size, 0.0);
+        assert!(compare(&[1.0], &[1.0, 2.0]).is_none());
+    }
+}
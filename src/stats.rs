@@ -4,16 +4,24 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use serde::{Deserialize, Serialize};
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::metric_key::MetricKey;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Stats {
-    pub metrics: HashMap<String, u64>,
+    #[serde(deserialize_with = "deserialize_metrics")]
+    pub metrics: HashMap<String, Value>,
     pub commit: String,
     pub timestamp: u128,
     pub commit_timestamp: u128,
     pub rust: Rust,
     pub host: Host,
+    /// Only some metrics were collected, like when bisecting, and the commit still needs a
+    /// full run
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -28,17 +36,337 @@ pub struct Host {
     pub os_version: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    #[serde(rename = "bytes")]
+    Bytes,
+    #[serde(rename = "ns")]
+    Nanoseconds,
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "fps")]
+    Fps,
+    #[serde(rename = "percent")]
+    Percent,
+    #[serde(rename = "count")]
+    Count,
+}
+
+impl Unit {
+    /// Renders a value in this unit for humans, like `1.52 ms` or `48.31 MiB`.
+    pub fn format(&self, value: f64) -> String {
+        match self {
+            Unit::Bytes => {
+                let mut value = value;
+                let mut prefixes = ["B", "KiB", "MiB", "GiB"].into_iter().peekable();
+                while value.abs() >= 1024.0 && prefixes.len() > 1 {
+                    value /= 1024.0;
+                    prefixes.next();
+                }
+                format!("{value:.2} {}", prefixes.next().unwrap())
+            }
+            Unit::Nanoseconds => format_duration(value),
+            Unit::Milliseconds => format_duration(value * 1_000_000.0),
+            Unit::Fps => format!("{value:.1} fps"),
+            Unit::Percent => format!("{value:.1}%"),
+            Unit::Count if value.fract() == 0.0 => format!("{value}"),
+            Unit::Count => format!("{value:.2}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Unit::Bytes => "bytes",
+            Unit::Nanoseconds => "ns",
+            Unit::Milliseconds => "ms",
+            Unit::Fps => "fps",
+            Unit::Percent => "percent",
+            Unit::Count => "count",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Unit {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "bytes" => Ok(Unit::Bytes),
+            "ns" => Ok(Unit::Nanoseconds),
+            "ms" => Ok(Unit::Milliseconds),
+            "fps" => Ok(Unit::Fps),
+            "percent" => Ok(Unit::Percent),
+            "count" => Ok(Unit::Count),
+            _ => Err(format!("unknown unit: {name}")),
+        }
+    }
+}
+
+fn format_duration(ns: f64) -> String {
+    match ns.abs() {
+        d if d >= 60_000_000_000.0 => {
+            format!(
+                "{}m {:.0}s",
+                (ns / 60_000_000_000.0).trunc(),
+                (ns / 1_000_000_000.0) % 60.0
+            )
+        }
+        d if d >= 1_000_000_000.0 => format!("{:.2} s", ns / 1_000_000_000.0),
+        d if d >= 1_000_000.0 => format!("{:.2} ms", ns / 1_000_000.0),
+        d if d >= 1_000.0 => format!("{:.2} µs", ns / 1_000.0),
+        _ => format!("{ns:.2} ns"),
+    }
+}
+
+/// A measured value, with the bounds of its confidence interval when the tool gives them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Value {
+    pub value: f64,
+    pub unit: Unit,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lower: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upper: Option<f64>,
+}
+
+impl Value {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self {
+            value,
+            unit,
+            lower: None,
+            upper: None,
+        }
+    }
+
+    pub fn bytes(value: u64) -> Self {
+        Self::new(value as f64, Unit::Bytes)
+    }
+
+    pub fn count(value: u64) -> Self {
+        Self::new(value as f64, Unit::Count)
+    }
+
+    pub fn with_bounds(mut self, lower: Option<f64>, upper: Option<f64>) -> Self {
+        self.lower = lower;
+        self.upper = upper;
+        self
+    }
+
+    /// Converts a value from before units were stored, when everything was an integer scaled
+    /// so that it didn't lose too much precision.
+    pub fn from_legacy(key: &str, raw: u64) -> Self {
+        match key.parse::<MetricKey>() {
+            Ok(key) => Self::new(raw as f64 / key.legacy_scale(), key.unit()),
+            Err(_) => Self::count(raw),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.unit.format(self.value))
+    }
+}
+
+fn deserialize_metrics<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Value>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Value(Value),
+        Legacy(u64),
+    }
+
+    let stored = HashMap::<String, Stored>::deserialize(deserializer)?;
+    let mut metrics = HashMap::with_capacity(stored.len());
+    let mut bounds = vec![];
+    for (key, stored) in stored {
+        let value = match stored {
+            Stored::Value(value) => value,
+            Stored::Legacy(raw) => {
+                // Bounds of benchmarks used to be separate metrics
+                if key.starts_with("benchmarks.")
+                    && let Some(base) = key
+                        .strip_suffix("_lower")
+                        .map(|base| (base, true))
+                        .or_else(|| key.strip_suffix("_upper").map(|base| (base, false)))
+                {
+                    bounds.push((base.0.to_string(), base.1, Value::from_legacy(&key, raw)));
+                    continue;
+                }
+                Value::from_legacy(&key, raw)
+            }
+        };
+        metrics.insert(key, value);
+    }
+    for (key, is_lower, bound) in bounds {
+        match metrics.get_mut(&key) {
+            Some(value) if is_lower => value.lower = Some(bound.value),
+            Some(value) => value.upper = Some(bound.value),
+            None => {
+                let suffix = if is_lower { "_lower" } else { "_upper" };
+                metrics.insert(format!("{key}{suffix}"), bound);
+            }
+        }
+    }
+    Ok(metrics)
+}
+
 pub fn find_stats_files(root: &Path) -> Vec<PathBuf> {
     let mut files = Vec::new();
     if let Ok(entries) = fs::read_dir(root) {
         for entry in entries.flatten() {
-            if entry.file_type().unwrap().is_file() && entry.file_name() == "stats.json" {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_file() && entry.file_name() == "stats.json" {
                 files.push(entry.path());
             }
-            if entry.file_type().unwrap().is_dir() {
+            if file_type.is_dir() {
                 files.extend(find_stats_files(&entry.path()));
             }
         }
     }
     files
 }
+
+/// A `stats.json` that couldn't be loaded.
+#[derive(Debug)]
+pub struct LoadError {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "skipping {}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Reads a single `stats.json`.
+pub fn read_stats(path: &Path) -> Result<Stats, LoadError> {
+    let content = fs::read(path).map_err(|error| LoadError {
+        path: path.to_path_buf(),
+        error: error.to_string(),
+    })?;
+    parse_stats(path, &content)
+}
+
+pub(crate) fn parse_stats(path: &Path, content: &[u8]) -> Result<Stats, LoadError> {
+    serde_json::from_slice(content).map_err(|error| LoadError {
+        path: path.to_path_buf(),
+        error: error.to_string(),
+    })
+}
+
+/// Selects stats from the results, every field left to `None` matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Only keep metrics whose name starts with this
+    pub metric_prefix: Option<String>,
+    /// Commit hash, or a prefix of it
+    pub commit: Option<String>,
+    /// Earliest commit timestamp, in milliseconds
+    pub since: Option<u128>,
+    /// Latest commit timestamp, in milliseconds
+    pub until: Option<u128>,
+    pub hostname: Option<String>,
+}
+
+impl Query {
+    pub fn metric_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.metric_prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn commit(mut self, commit: impl Into<String>) -> Self {
+        self.commit = Some(commit.into());
+        self
+    }
+
+    pub fn since(mut self, since: chrono::DateTime<chrono::Utc>) -> Self {
+        self.since = Some(since.timestamp_millis() as u128);
+        self
+    }
+
+    pub fn until(mut self, until: chrono::DateTime<chrono::Utc>) -> Self {
+        self.until = Some(until.timestamp_millis() as u128);
+        self
+    }
+
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Whether the commit, date and host of `stats` are selected.
+    pub fn matches(&self, stats: &Stats) -> bool {
+        self.commit
+            .as_ref()
+            .is_none_or(|commit| stats.commit.starts_with(commit.as_str()))
+            && self
+                .since
+                .is_none_or(|since| stats.commit_timestamp >= since)
+            && self
+                .until
+                .is_none_or(|until| stats.commit_timestamp <= until)
+            && self
+                .hostname
+                .as_ref()
+                .is_none_or(|hostname| &stats.host.hostname == hostname)
+    }
+
+    pub fn keeps_metric(&self, name: &str) -> bool {
+        self.metric_prefix
+            .as_ref()
+            .is_none_or(|prefix| name.starts_with(prefix.as_str()))
+    }
+}
+
+/// Loads every `stats.json` under `root` matching `filter`, sorted by commit timestamp.
+///
+/// Files are read in parallel. The ones that can't be read or parsed are reported and skipped,
+/// so that a single broken file doesn't prevent using all the others. Metrics not matching the
+/// prefix of the filter are dropped as soon as a file is parsed.
+pub fn load_results(root: &Path, filter: &Query) -> Vec<Stats> {
+    let mut stats = find_stats_files(root)
+        .par_iter()
+        .filter_map(|path| match read_stats(path) {
+            Ok(stats) => Some(stats),
+            Err(error) => {
+                eprintln!("{error}");
+                None
+            }
+        })
+        .filter(|stats| filter.matches(stats))
+        .map(|mut stats| {
+            stats.metrics.retain(|name, _| filter.keeps_metric(name));
+            stats
+        })
+        .collect::<Vec<_>>();
+    stats.sort_by_key(|stats| stats.commit_timestamp);
+    stats
+}
+
+/// Finds the results folder of a commit from a prefix of its hash.
+pub fn find_commit_dir(root: &Path, commit: &str) -> Option<PathBuf> {
+    let mut chars = commit.chars();
+    let folder = root
+        .join(chars.next()?.to_string())
+        .join(chars.next()?.to_string());
+    let mut candidates = fs::read_dir(folder)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(commit))
+        .map(|entry| entry.path());
+    let found = candidates.next()?;
+    // An ambiguous prefix doesn't designate a commit
+    candidates.next().is_none().then_some(found)
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use rusqlite::{Connection, params};
+
+use crate::{
+    benchmark_ids::fnv1a,
+    stats::{Host, Query, Rust, Stats, Value, find_stats_files, load_results, parse_stats},
+};
+
+/// Where the index of a results folder is kept, next to it so that it's never committed with
+/// the results.
+pub fn default_index(root: &Path) -> PathBuf {
+    root.with_extension("sqlite")
+}
+
+/// Loads the stats matching `query`, after bringing the index of `root` up to date.
+///
+/// If the index can't be used, the files are read directly instead.
+pub fn load(root: &Path, query: &Query) -> Vec<Stats> {
+    let indexed = ResultsStore::open(&default_index(root)).and_then(|mut store| {
+        store.update(root)?;
+        store.query(query)
+    });
+    match indexed {
+        Ok(stats) => stats,
+        Err(error) => {
+            eprintln!("results index unavailable, reading all files: {error}");
+            load_results(root, query)
+        }
+    }
+}
+
+/// Version of the layout of the index, to bump when it changes.
+const SCHEMA_VERSION: i32 = 1;
+
+/// An SQLite index of every `stats.json` in a results folder.
+///
+/// Files are only parsed again when their content changed, so updating the index after a new
+/// run only parses the new results. Content is compared with a hash rather than with the
+/// modification time, as that one is reset on every checkout of the results.
+pub struct ResultsStore {
+    connection: Connection,
+}
+
+impl ResultsStore {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        // The index is only a cache of the files, rebuild it when its layout changes
+        let version: i32 = connection.query_row("pragma user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            connection.execute_batch(
+                "
+                drop table if exists files;
+                drop table if exists stats;
+                drop table if exists metrics;
+                ",
+            )?;
+            connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        connection.execute_batch(
+            "
+            pragma journal_mode = wal;
+            create table if not exists files (
+                path text primary key,
+                hash text not null,
+                commit_hash text not null
+            );
+            create table if not exists stats (
+                commit_hash text primary key,
+                timestamp integer not null,
+                commit_timestamp integer not null,
+                rust_stable text not null,
+                rust_nightly text not null,
+                hostname text not null,
+                os_version text not null,
+                partial integer not null
+            );
+            create index if not exists stats_commit_timestamp on stats (commit_timestamp);
+            create table if not exists metrics (
+                commit_hash text not null,
+                name text not null,
+                value real not null,
+                unit text not null,
+                lower real,
+                upper real,
+                primary key (commit_hash, name)
+            ) without rowid;
+            create index if not exists metrics_name on metrics (name);
+            ",
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Indexes the stats files of `root` that are new or changed, and forgets the ones that
+    /// were removed. Returns the number of files that were read.
+    pub fn update(&mut self, root: &Path) -> rusqlite::Result<usize> {
+        let files = find_stats_files(root);
+        let transaction = self.connection.transaction()?;
+
+        let mut known: HashMap<String, (String, String)> = transaction
+            .prepare("select path, hash, commit_hash from files")?
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        // Hashing and parsing are done in parallel, only writing to the index is sequential
+        let changed = files
+            .par_iter()
+            .map(|file| {
+                let path = file.to_string_lossy().to_string();
+                let content = match std::fs::read(file) {
+                    Ok(content) => content,
+                    Err(error) => {
+                        // Keep what was indexed, the file may only be unreadable for now
+                        eprintln!("skipping {path}: {error}");
+                        return (path, None);
+                    }
+                };
+                let hash = format!("{:016x}", fnv1a(&content));
+                if known
+                    .get(&path)
+                    .is_some_and(|(known_hash, _)| *known_hash == hash)
+                {
+                    return (path, None);
+                }
+                let parsed = parse_stats(file, &content);
+                (path, Some((hash, parsed)))
+            })
+            .collect::<Vec<_>>();
+
+        let mut read = 0;
+        for (path, changed) in changed {
+            let previous = known.remove(&path);
+            let Some((hash, parsed)) = changed else {
+                continue;
+            };
+            let stats = match parsed {
+                Ok(stats) => stats,
+                Err(error) => {
+                    eprintln!("{error}");
+                    // Forget what the file contained before it broke
+                    if let Some((_, commit)) = previous {
+                        forget_file(&transaction, &path, &commit)?;
+                    }
+                    continue;
+                }
+            };
+            read += 1;
+
+            remove_commit(&transaction, &stats.commit)?;
+            transaction.execute(
+                "insert into stats values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    stats.commit,
+                    stats.timestamp as i64,
+                    stats.commit_timestamp as i64,
+                    stats.rust.stable,
+                    stats.rust.nightly,
+                    stats.host.hostname,
+                    stats.host.os_version,
+                    stats.partial,
+                ],
+            )?;
+            let mut insert = transaction
+                .prepare_cached("insert into metrics values (?1, ?2, ?3, ?4, ?5, ?6)")?;
+            for (name, value) in &stats.metrics {
+                insert.execute(params![
+                    stats.commit,
+                    name,
+                    value.value,
+                    value.unit.to_string(),
+                    value.lower,
+                    value.upper,
+                ])?;
+            }
+            transaction.execute(
+                "insert or replace into files values (?1, ?2, ?3)",
+                params![path, hash, stats.commit],
+            )?;
+        }
+
+        // Whatever is left was removed from the results
+        for (path, (_, commit)) in known {
+            forget_file(&transaction, &path, &commit)?;
+        }
+
+        // Only one of them is indexed, whichever was read last
+        let duplicates = transaction
+            .prepare(
+                "select commit_hash, group_concat(path, ', ') from files
+                group by commit_hash having count(*) > 1",
+            )?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (commit, paths) in duplicates {
+            eprintln!("several stats files for commit {commit}: {paths}");
+        }
+
+        transaction.commit()?;
+        Ok(read)
+    }
+
+    pub fn query(&self, query: &Query) -> rusqlite::Result<Vec<Stats>> {
+        let commit_pattern = query.commit.as_ref().map(|commit| format!("{commit}%"));
+        let filters = "(?1 is null or s.commit_hash like ?1)
+            and (?2 is null or s.commit_timestamp >= ?2)
+            and (?3 is null or s.commit_timestamp <= ?3)
+            and (?4 is null or s.hostname = ?4)";
+        let filter_params = params![
+            commit_pattern,
+            query.since.map(|since| since as i64),
+            query.until.map(|until| until as i64),
+            query.hostname,
+        ];
+
+        let mut stats: HashMap<String, Stats> = self
+            .connection
+            .prepare(&format!("select s.* from stats s where {filters}"))?
+            .query_map(filter_params, |row| {
+                Ok(Stats {
+                    metrics: HashMap::new(),
+                    commit: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u128,
+                    commit_timestamp: row.get::<_, i64>(2)? as u128,
+                    rust: Rust {
+                        stable: row.get(3)?,
+                        nightly: row.get(4)?,
+                    },
+                    host: Host {
+                        hostname: row.get(5)?,
+                        os_version: row.get(6)?,
+                    },
+                    partial: row.get(7)?,
+                })
+            })?
+            .map(|stats| stats.map(|stats| (stats.commit.clone(), stats)))
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut statement = self.connection.prepare(&format!(
+            "select m.* from metrics m join stats s on s.commit_hash = m.commit_hash
+            where {filters} and (?5 is null or (m.name >= ?5 and m.name < ?6))"
+        ))?;
+        // Upper bound of the names starting with the prefix, so that the index on names is used
+        let prefix_end = query
+            .metric_prefix
+            .as_ref()
+            .map(|prefix| format!("{prefix}\u{10ffff}"));
+        let mut rows = statement.query(params![
+            commit_pattern,
+            query.since.map(|since| since as i64),
+            query.until.map(|until| until as i64),
+            query.hostname,
+            query.metric_prefix,
+            prefix_end,
+        ])?;
+        while let Some(row) = rows.next()? {
+            let commit: String = row.get(0)?;
+            let unit: String = row.get(3)?;
+            let Some(stats) = stats.get_mut(&commit) else {
+                continue;
+            };
+            stats.metrics.insert(
+                row.get(1)?,
+                Value {
+                    value: row.get(2)?,
+                    unit: unit.parse().map_err(|_| {
+                        rusqlite::Error::InvalidColumnType(
+                            3,
+                            "unit".to_string(),
+                            rusqlite::types::Type::Text,
+                        )
+                    })?,
+                    lower: row.get(4)?,
+                    upper: row.get(5)?,
+                },
+            );
+        }
+
+        let mut stats = stats.into_values().collect::<Vec<_>>();
+        stats.sort_by_key(|stats| stats.commit_timestamp);
+        Ok(stats)
+    }
+
+    /// The stats of a single commit, from its hash or an unambiguous prefix of it.
+    pub fn commit(&self, commit: &str) -> rusqlite::Result<Option<Stats>> {
+        let mut found = self.query(&Query::default().commit(commit))?;
+        Ok((found.len() == 1).then(|| found.remove(0)))
+    }
+}
+
+/// Removes a file from the index, and its commit unless another file has results for it.
+fn forget_file(connection: &Connection, path: &str, commit: &str) -> rusqlite::Result<()> {
+    connection.execute("delete from files where path = ?1", params![path])?;
+    let still_indexed = connection
+        .prepare("select 1 from files where commit_hash = ?1")?
+        .exists(params![commit])?;
+    if !still_indexed {
+        remove_commit(connection, commit)?;
+    }
+    Ok(())
+}
+
+fn remove_commit(connection: &Connection, commit: &str) -> rusqlite::Result<()> {
+    connection.execute("delete from stats where commit_hash = ?1", params![commit])?;
+    connection.execute(
+        "delete from metrics where commit_hash = ?1",
+        params![commit],
+    )?;
+    Ok(())
+}